@@ -0,0 +1,285 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use crate::config::{load_json, save_json};
+use crate::history::{self, FileOutcome, JobOptions};
+use crate::pipeline::{self, ProgressUpdate};
+use crate::providers::{BatchStatus, OcrOptions, OcrProvider, OcrResult};
+use crate::providers::registry::{self, SettingsMap};
+
+// 两次查询批量任务状态之间的间隔
+pub const POLL_INTERVAL_SECS: u64 = 60;
+
+// 批量任务中的一个请求：一个文件，或大 PDF 拆分后的一个分块
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchRequest {
+    pub input: PathBuf,
+    pub page_offset: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum BatchState {
+    // 已提交，等待供应商处理：(已完成的请求数, 请求总数)
+    Pending { completed: usize, total: usize },
+    Completed,
+    Failed(String),
+}
+
+// 已提交的批量任务，保存到磁盘以便应用重启后继续查询
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchJob {
+    // 供应商返回的任务 ID
+    pub id: String,
+    pub provider: String,
+    // Unix timestamp (seconds)
+    pub submitted_at: i64,
+    pub options: JobOptions,
+    #[serde(default)]
+    pub model: Option<String>,
+    // 顺序与提交给供应商的请求一致
+    pub requests: Vec<BatchRequest>,
+    pub state: BatchState,
+    // 之前的查询中已保存的文件，重试保存时跳过
+    #[serde(default)]
+    pub saved: Vec<FileOutcome>,
+}
+
+impl BatchJob {
+    pub fn is_pending(&self) -> bool {
+        matches!(self.state, BatchState::Pending { .. })
+    }
+
+    // 去重后的输入文件，保持提交顺序
+    pub fn inputs(&self) -> Vec<PathBuf> {
+        let mut inputs: Vec<PathBuf> = Vec::new();
+        for request in &self.requests {
+            if !inputs.contains(&request.input) {
+                inputs.push(request.input.clone());
+            }
+        }
+        inputs
+    }
+
+    pub fn submitted_at_display(&self) -> String {
+        history::format_timestamp(self.submitted_at)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BatchJobs {
+    pub jobs: Vec<BatchJob>,
+}
+
+impl BatchJobs {
+    pub fn update_state(&mut self, id: &str, state: BatchState) {
+        if let Some(job) = self.jobs.iter_mut().find(|j| j.id == id) {
+            job.state = state;
+        }
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        self.jobs.retain(|j| j.id != id);
+    }
+}
+
+// 批量任务的轮询结果，由后台任务发回界面
+pub enum BatchUpdate {
+    State(String, BatchState),
+    // 结果已保存：(任务 ID, 每个文件的结果, 汇总或打包的错误)
+    Finished(String, Vec<FileOutcome>, Option<String>),
+    // 部分结果未能保存：(任务 ID, 已保存的文件, 错误)；任务保持等待状态，下次查询时重试
+    Incomplete(String, Vec<FileOutcome>, String),
+    // 查询或保存失败 (网络、API Key、结果无法解析等)：任务保持等待状态，下次再试
+    Error(String, String),
+    PollDone,
+}
+
+pub fn load_batch_jobs() -> BatchJobs {
+    load_json("batch_jobs")
+}
+
+pub fn save_batch_jobs(jobs: &BatchJobs) -> Result<()> {
+    save_json("batch_jobs", jobs)
+}
+
+// 准备所有输入 (图片转换、大文件拆分) 并作为一个批量任务提交
+pub async fn submit(
+    provider: &dyn OcrProvider,
+    files: &[PathBuf],
+    options: JobOptions,
+    ocr_options: &OcrOptions,
+    tx: &mpsc::Sender<ProgressUpdate>,
+) -> Result<BatchJob> {
+    let mut prepared = Vec::new();
+    let mut requests = Vec::new();
+    for file in files {
+        let input = pipeline::prepare_input(file, provider.capabilities(), tx).await?;
+        for chunk in &input.chunks {
+            requests.push(BatchRequest { input: file.clone(), page_offset: chunk.page_offset });
+        }
+        prepared.push(input);
+    }
+
+    let _ = tx.send(ProgressUpdate::Message(format!("{}: uploading {} requests...", provider.name(), requests.len()))).await;
+    let paths: Vec<PathBuf> = prepared.iter()
+        .flat_map(|p| p.chunks.iter().map(|c| c.path.clone()))
+        .collect();
+    let id = provider.submit_batch(&paths, ocr_options).await?;
+
+    let total = requests.len();
+    Ok(BatchJob {
+        id,
+        provider: provider.id().to_string(),
+        submitted_at: chrono::Local::now().timestamp(),
+        options,
+        model: provider.model().map(str::to_string),
+        requests,
+        state: BatchState::Pending { completed: 0, total },
+        saved: Vec::new(),
+    })
+}
+
+// 查询所有未完成的任务；完成的任务会下载结果并按普通流程保存
+pub async fn poll_jobs(jobs: Vec<BatchJob>, settings: SettingsMap, tx: mpsc::Sender<BatchUpdate>) {
+    for job in jobs {
+        let update = match poll_job(&job, &settings).await {
+            Ok(update) => update,
+            Err(e) => BatchUpdate::Error(job.id.clone(), e.to_string()),
+        };
+        let _ = tx.send(update).await;
+    }
+    let _ = tx.send(BatchUpdate::PollDone).await;
+}
+
+async fn poll_job(job: &BatchJob, settings: &SettingsMap) -> Result<BatchUpdate> {
+    let provider = registry::build(&job.provider, settings)?;
+    let update = match provider.poll_batch(&job.id).await? {
+        BatchStatus::Running { completed, total } => {
+            BatchUpdate::State(job.id.clone(), BatchState::Pending { completed, total })
+        }
+        BatchStatus::Failed(error) => BatchUpdate::State(job.id.clone(), BatchState::Failed(error)),
+        BatchStatus::Completed(results) => {
+            // 供应商返回错误的文件本来就没有结果可保存
+            let failed: Vec<PathBuf> = job.requests.iter()
+                .zip(&results)
+                .filter(|(_, result)| result.is_err())
+                .map(|(request, _)| request.input.clone())
+                .collect();
+            let saved_job = job.clone();
+            let files = tokio::task::spawn_blocking(move || save_results(&saved_job, results)).await?;
+            // 有结果没能写入磁盘时保留供应商端的文件，下次查询时重新下载并保存
+            let unsaved: Vec<String> = files.iter()
+                .filter(|f| f.output_dir.is_none() && !failed.contains(&f.input))
+                .map(|f| format!("{}: {}", f.input.display(), f.error.as_deref().unwrap_or_default()))
+                .collect();
+            if !unsaved.is_empty() {
+                let saved = files.into_iter().filter(|f| f.output_dir.is_some()).collect();
+                return Ok(BatchUpdate::Incomplete(job.id.clone(), saved, unsaved.join("; ")));
+            }
+
+            let options = job.options.clone();
+            let out_dirs: Vec<PathBuf> = files.iter().filter_map(|f| f.output_dir.clone()).collect();
+            let job_error = tokio::task::spawn_blocking(move || pipeline::finish_job(&options, &out_dirs)).await?
+                .err()
+                .map(|e| e.to_string());
+            let _ = provider.finish_batch(&job.id).await;
+            BatchUpdate::Finished(job.id.clone(), files, job_error)
+        }
+    };
+    Ok(update)
+}
+
+// 将每个请求的结果写入对应文档的输出目录；任一分块失败则整个文件记为失败
+pub fn save_results(job: &BatchJob, results: Vec<Result<OcrResult, String>>) -> Vec<FileOutcome> {
+    let mut results: Vec<Option<Result<OcrResult, String>>> = results.into_iter().map(Some).collect();
+    job.inputs()
+        .into_iter()
+        .map(|input| {
+            if let Some(saved) = job.saved.iter().find(|f| f.input == input) {
+                return saved.clone();
+            }
+            let chunks: Vec<(u32, Result<OcrResult, String>)> = job.requests.iter()
+                .enumerate()
+                .filter(|(_, r)| r.input == input)
+                .map(|(i, r)| {
+                    let result = results.get_mut(i)
+                        .and_then(|r| r.take())
+                        .unwrap_or_else(|| Err("Missing from batch output".to_string()));
+                    (r.page_offset, result)
+                })
+                .collect();
+            match save_document(job, &input, chunks) {
+                Ok(out_dir) => FileOutcome { input, output_dir: Some(out_dir), error: None },
+                Err(e) => FileOutcome { input, output_dir: None, error: Some(e.to_string()) },
+            }
+        })
+        .collect()
+}
+
+fn save_document(job: &BatchJob, input: &Path, chunks: Vec<(u32, Result<OcrResult, String>)>) -> Result<PathBuf> {
+    let chunks = chunks.into_iter()
+        .map(|(page_offset, result)| result.map(|r| (page_offset, r)).map_err(|e| anyhow::anyhow!(e)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let target = job.options.output.layout.resolve(&job.options.output_base, input, &job.provider)?;
+    if target.skip {
+        return Ok(target.dir);
+    }
+
+    let mut pages = Vec::new();
+    let mut output = pipeline::DocumentOutput::new(input, &target, &job.options)?;
+    for (page_offset, result) in chunks {
+        pages.extend(pipeline::save_ocr_results(result, &job.provider, page_offset, &mut output)?);
+    }
+
+    pipeline::finish_document(&output, &job.provider, job.model.as_deref(), pages, &job.options)?;
+    Ok(output.out_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::SavedDocument;
+    use crate::providers::mock::MockProvider;
+
+    #[test]
+    fn results_are_routed_to_their_documents() {
+        let dir = tempfile::tempdir().unwrap();
+        let job = BatchJob {
+            id: "job-1".into(),
+            provider: "mistral".into(),
+            submitted_at: 0,
+            options: JobOptions { output_base: dir.path().to_path_buf(), ..Default::default() },
+            model: None,
+            requests: vec![
+                BatchRequest { input: "big.pdf".into(), page_offset: 0 },
+                BatchRequest { input: "big.pdf".into(), page_offset: 2 },
+                BatchRequest { input: "bad.pdf".into(), page_offset: 0 },
+            ],
+            state: BatchState::Pending { completed: 3, total: 3 },
+            saved: Vec::new(),
+        };
+        let results = vec![
+            Ok(MockProvider::simple_result(2, false)),
+            Ok(MockProvider::simple_result(1, false)),
+            Err("HTTP 422: invalid document".to_string()),
+        ];
+
+        let files = save_results(&job, results);
+
+        assert_eq!(files.len(), 2);
+        let doc = SavedDocument::load(files[0].output_dir.as_ref().unwrap()).unwrap();
+        assert_eq!(doc.pages.iter().map(|p| p.number).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert!(dir.path().join("ocr_big/complete.md").exists());
+        assert_eq!(files[1].input, PathBuf::from("bad.pdf"));
+        assert!(files[1].error.as_deref().unwrap().contains("invalid document"));
+        assert!(!dir.path().join("ocr_bad").exists());
+
+        // 重试保存时，之前已保存的文件直接沿用
+        let retry = BatchJob { saved: vec![files[0].clone()], ..job };
+        let files = save_results(&retry, vec![Err("gone".into()), Err("gone".into()), Err("gone".into())]);
+        assert_eq!(files[0].output_dir, Some(dir.path().join("ocr_big")));
+        assert!(files[1].error.is_some());
+    }
+}
@@ -0,0 +1,214 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use crate::export::{self, SavedDocument};
+
+// 整批打包时写入压缩包根目录的清单
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+// 识别结果的打包方式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BundleMode {
+    #[default]
+    None,
+    // 每个文档目录旁生成 <目录名>.zip
+    PerDocument,
+    // 每次任务在输出目录生成一个包含所有文档和清单的 zip
+    PerJob,
+}
+
+impl BundleMode {
+    pub const ALL: [BundleMode; 3] = [BundleMode::None, BundleMode::PerDocument, BundleMode::PerJob];
+
+    // i18n key
+    pub fn label(&self) -> &'static str {
+        match self {
+            BundleMode::None => "bundle_none",
+            BundleMode::PerDocument => "bundle_per_document",
+            BundleMode::PerJob => "bundle_per_job",
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct ManifestFile {
+    path: String,
+    size: u64,
+    sha256: String,
+}
+
+#[derive(Serialize, Debug)]
+struct ManifestDocument {
+    source: PathBuf,
+    provider: String,
+    pages: usize,
+    // 文档在压缩包中的目录
+    dir: String,
+    files: Vec<ManifestFile>,
+}
+
+#[derive(Serialize, Debug)]
+struct Manifest {
+    documents: Vec<ManifestDocument>,
+}
+
+// 文档目录中需要打包的文件 (相对路径)，按路径排序；不含 debug/ 中的中间文件
+fn document_files(out_dir: &Path) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(out_dir).sort_by_file_name() {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(out_dir)?;
+        if relative.starts_with(export::DEBUG_DIR) {
+            continue;
+        }
+        // zip 中统一使用 / 作为分隔符
+        let name = relative.components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        files.push(name);
+    }
+    files.sort();
+    Ok(files)
+}
+
+// 固定的修改时间和权限，相同内容得到完全相同的压缩包
+fn entry_options() -> SimpleFileOptions {
+    SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .last_modified_time(zip::DateTime::default())
+        .unix_permissions(0o644)
+}
+
+fn document_bundle_path(out_dir: &Path) -> PathBuf {
+    let name = out_dir.file_name().unwrap_or_default().to_string_lossy();
+    out_dir.with_file_name(format!("{}.zip", name))
+}
+
+// 把一个文档目录打包为同级的 <目录名>.zip
+pub fn bundle_document(out_dir: &Path) -> Result<PathBuf> {
+    let zip_path = document_bundle_path(out_dir);
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path)?);
+    for file in document_files(out_dir)? {
+        zip.start_file(file.as_str(), entry_options())?;
+        zip.write_all(&std::fs::read(out_dir.join(&file))?)?;
+    }
+    zip.finish()?;
+    Ok(zip_path)
+}
+
+// 结果被修改或重新导出后，重新生成已有的单文档压缩包，避免其内容过时
+pub fn refresh_document_bundle(out_dir: &Path) -> Result<Option<PathBuf>> {
+    if !document_bundle_path(out_dir).exists() {
+        return Ok(None);
+    }
+    bundle_document(out_dir).map(Some)
+}
+
+// 把一次任务的所有文档打包到输出目录下带时间戳的 zip，附带清单
+pub fn bundle_job(output_base: &Path, out_dirs: &[PathBuf]) -> Result<Option<PathBuf>> {
+    if out_dirs.is_empty() {
+        return Ok(None);
+    }
+    let zip_path = output_base.join(format!("ocr_bundle_{}.zip", chrono::Local::now().format("%Y%m%d_%H%M%S")));
+    write_job_bundle(&zip_path, output_base, out_dirs)?;
+    Ok(Some(zip_path))
+}
+
+fn write_job_bundle(zip_path: &Path, output_base: &Path, out_dirs: &[PathBuf]) -> Result<()> {
+    // 文档在压缩包中的目录：相对于输出目录的路径
+    let mut documents: Vec<(String, &PathBuf)> = out_dirs.iter()
+        .map(|dir| {
+            let relative = dir.strip_prefix(output_base).unwrap_or(dir.as_path());
+            let prefix = relative.components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            (prefix, dir)
+        })
+        .collect();
+    documents.sort();
+    documents.dedup();
+
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(zip_path)?);
+    let mut manifest = Manifest { documents: Vec::new() };
+    for (prefix, dir) in documents {
+        let doc = SavedDocument::load(dir)?;
+        let mut files = Vec::new();
+        for file in document_files(dir)? {
+            let bytes = std::fs::read(dir.join(&file))?;
+            let path = format!("{}/{}", prefix, file);
+            zip.start_file(path.as_str(), entry_options())?;
+            zip.write_all(&bytes)?;
+            files.push(ManifestFile { path, size: bytes.len() as u64, sha256: format!("{:x}", Sha256::digest(&bytes)) });
+        }
+        manifest.documents.push(ManifestDocument {
+            source: doc.source,
+            provider: doc.provider,
+            pages: doc.pages.len(),
+            dir: prefix,
+            files,
+        });
+    }
+    zip.start_file(MANIFEST_FILE, entry_options())?;
+    zip.write_all(serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+    zip.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_document(dir: &Path) {
+        std::fs::create_dir_all(dir.join("images")).unwrap();
+        std::fs::create_dir_all(dir.join(export::DEBUG_DIR)).unwrap();
+        std::fs::write(dir.join("complete.md"), "## Page 1").unwrap();
+        std::fs::write(dir.join("images/page1_img-0.png"), [1, 2, 3]).unwrap();
+        std::fs::write(dir.join(export::DEBUG_DIR).join("part_0.md"), "x").unwrap();
+        SavedDocument {
+            source: "doc.pdf".into(),
+            provider: "mock".into(),
+            pages: Vec::new(),
+            ..Default::default()
+        }.save(dir).unwrap();
+    }
+
+    #[test]
+    fn bundles_are_ordered_and_reproducible() {
+        let dir = tempfile::tempdir().unwrap();
+        let out_dir = dir.path().join("ocr_doc");
+        write_document(&out_dir);
+
+        let zip_path = bundle_document(&out_dir).unwrap();
+        assert_eq!(zip_path, dir.path().join("ocr_doc.zip"));
+        let first = std::fs::read(&zip_path).unwrap();
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(&first)).unwrap();
+        let names: Vec<String> = (0..archive.len()).map(|i| archive.by_index(i).unwrap().name().to_string()).collect();
+        assert_eq!(names, vec!["complete.md", "images/page1_img-0.png", "result.json"]);
+        assert_eq!(std::fs::read(bundle_document(&out_dir).unwrap()).unwrap(), first);
+
+        // 修改结果后重新生成已有的压缩包；没有压缩包的目录不会新建
+        std::fs::write(out_dir.join("complete.md"), "## Page 1\n\nEdited").unwrap();
+        assert_eq!(refresh_document_bundle(&out_dir).unwrap(), Some(zip_path.clone()));
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&zip_path).unwrap()).unwrap();
+        assert!(std::io::read_to_string(archive.by_name("complete.md").unwrap()).unwrap().ends_with("Edited"));
+        let other = dir.path().join("ocr_other");
+        write_document(&other);
+        assert_eq!(refresh_document_bundle(&other).unwrap(), None);
+
+        let job_zip = dir.path().join("job.zip");
+        write_job_bundle(&job_zip, dir.path(), &[out_dir]).unwrap();
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&job_zip).unwrap()).unwrap();
+        let manifest: serde_json::Value = serde_json::from_reader(archive.by_name(MANIFEST_FILE).unwrap()).unwrap();
+        assert_eq!(manifest["documents"][0]["dir"], "ocr_doc");
+        assert_eq!(manifest["documents"][0]["files"][1]["path"], "ocr_doc/images/page1_img-0.png");
+        assert!(archive.by_name("ocr_doc/complete.md").is_ok());
+    }
+}
@@ -0,0 +1,349 @@
+use serde::{Deserialize, Serialize};
+use crate::export::SavedDocument;
+use crate::stitch::is_cjk;
+
+// 分块导出：供检索 / 向量化使用的 JSONL，每行一个块。
+// 块在段落、表格等边界处切分，遇到标题时总是开始新块；块可以跨页，记录页码范围
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChunkUnit {
+    Chars,
+    // 估算值：中日韩文字每字约 1 个 token，其他文字约 4 个字符 1 个 token
+    #[default]
+    Tokens,
+}
+
+impl ChunkUnit {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChunkUnit::Chars => "chunk_chars",
+            ChunkUnit::Tokens => "chunk_tokens",
+        }
+    }
+
+    // 每个字符的长度，以 1/4 为单位，便于逐字累加
+    fn weight(&self, c: char) -> usize {
+        match self {
+            ChunkUnit::Tokens if !is_cjk(c) => 1,
+            _ => 4,
+        }
+    }
+
+    // 以 1/4 为单位的长度，可以直接相加
+    fn quarters(&self, text: &str) -> usize {
+        text.chars().map(|c| self.weight(c)).sum()
+    }
+
+    pub fn measure(&self, text: &str) -> usize {
+        self.quarters(text).div_ceil(4)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkOptions {
+    pub unit: ChunkUnit,
+    // 每块的最大长度
+    pub size: usize,
+    // 相邻块之间重复的长度
+    pub overlap: usize,
+}
+
+impl Default for ChunkOptions {
+    fn default() -> Self {
+        Self { unit: ChunkUnit::Tokens, size: 512, overlap: 64 }
+    }
+}
+
+// JSONL 中的一行
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub source: String,
+    pub index: usize,
+    pub page_start: u32,
+    pub page_end: u32,
+    // 所在的各级标题，从上到下
+    pub headings: Vec<String>,
+    pub text: String,
+    // 文本中引用的图片 (相对输出目录的路径)
+    pub images: Vec<String>,
+}
+
+// 页面中不可再分的一段：段落、表格、代码块或标题
+struct Block {
+    page: u32,
+    text: String,
+    heading: Option<usize>,
+}
+
+fn heading_level(line: &str) -> Option<usize> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    ((1..=6).contains(&level) && line[level..].starts_with(' ')).then_some(level)
+}
+
+// 按空行切分；代码块内的空行不切分，标题单独成段
+fn blocks(doc: &SavedDocument) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    for page in &doc.pages {
+        let mut current: Vec<&str> = Vec::new();
+        let mut in_fence = false;
+        let flush = |current: &mut Vec<&str>, blocks: &mut Vec<Block>| {
+            let text = current.join("\n").trim().to_string();
+            if !text.is_empty() {
+                blocks.push(Block { page: page.number, text, heading: None });
+            }
+            current.clear();
+        };
+        for line in page.markdown.lines() {
+            if line.trim_start().starts_with("```") {
+                in_fence = !in_fence;
+            }
+            if in_fence || line.trim_start().starts_with("```") {
+                current.push(line);
+            } else if line.trim().is_empty() {
+                flush(&mut current, &mut blocks);
+            } else if let Some(level) = heading_level(line.trim_start()) {
+                flush(&mut current, &mut blocks);
+                blocks.push(Block { page: page.number, text: line.trim().to_string(), heading: Some(level) });
+            } else {
+                current.push(line);
+            }
+        }
+        flush(&mut current, &mut blocks);
+    }
+    blocks
+}
+
+// 超长的段落在换行处切分，没有换行时在空白或中日韩文字之后切分
+fn split_block(text: &str, unit: ChunkUnit, size: usize) -> Vec<String> {
+    if unit.measure(text) <= size {
+        return vec![text.to_string()];
+    }
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut weight = 0;
+    let (mut line_break, mut word_break) = (None, None);
+    for (i, c) in text.char_indices() {
+        weight += unit.weight(c);
+        if weight.div_ceil(4) > size && i > start {
+            // 超出的字符本身是空白时直接在此切分
+            let cut = if c.is_whitespace() { i } else { line_break.or(word_break).unwrap_or(i) };
+            pieces.push(text[start..cut].trim().to_string());
+            start = cut;
+            weight = text[cut..i + c.len_utf8()].chars().map(|c| unit.weight(c)).sum();
+            (line_break, word_break) = (None, None);
+        }
+        let end = i + c.len_utf8();
+        if c == '\n' {
+            line_break = Some(end);
+        } else if c.is_whitespace() || is_cjk(c) {
+            word_break = Some(end);
+        }
+    }
+    pieces.push(text[start..].trim().to_string());
+    pieces.retain(|p| !p.is_empty());
+    pieces
+}
+
+// 文本末尾不超过 budget 的部分，从词的边界开始
+fn tail(text: &str, unit: ChunkUnit, budget: usize) -> Option<String> {
+    let mut weight = 0;
+    let mut start = text.len();
+    for (i, c) in text.char_indices().rev() {
+        weight += unit.weight(c);
+        if weight.div_ceil(4) > budget {
+            break;
+        }
+        start = i;
+    }
+    let tail = &text[start..];
+    // 从单词中间开始时跳到下一个空白之后
+    let tail = match text[..start].chars().last() {
+        Some(prev) if !prev.is_whitespace() && !is_cjk(prev) && !tail.starts_with(is_cjk) => {
+            tail.split_once(char::is_whitespace).map(|(_, rest)| rest).unwrap_or("")
+        }
+        _ => tail,
+    };
+    let tail = tail.trim();
+    (!tail.is_empty()).then(|| tail.to_string())
+}
+
+struct Builder<'a> {
+    doc: &'a SavedDocument,
+    options: ChunkOptions,
+    chunks: Vec<Chunk>,
+    // 当前块的内容：(页码, 文本)
+    pieces: Vec<(u32, String)>,
+    // pieces 连接后的长度 (以 1/4 为单位)，随 pieces 增量更新
+    quarters: usize,
+    // 当前块中是否有重叠部分和标题以外的内容
+    has_body: bool,
+    headings: Vec<(usize, String)>,
+}
+
+impl Builder<'_> {
+    fn text(pieces: &[(u32, String)]) -> String {
+        pieces.iter().map(|(_, t)| t.as_str()).collect::<Vec<_>>().join("\n\n")
+    }
+
+    // 加入 text 之后当前块的长度
+    fn quarters_with(&self, text: &str) -> usize {
+        let separator = if self.pieces.is_empty() { 0 } else { self.options.unit.quarters("\n\n") };
+        self.quarters + separator + self.options.unit.quarters(text)
+    }
+
+    fn add(&mut self, page: u32, text: String) {
+        self.quarters = self.quarters_with(&text);
+        self.pieces.push((page, text));
+    }
+
+    // 结束当前块，保留末尾不超过 overlap 的内容作为下一块的开头
+    fn flush(&mut self, overlap: usize) {
+        let pieces = std::mem::take(&mut self.pieces);
+        self.quarters = 0;
+        if self.has_body {
+            self.emit(&pieces);
+        }
+        self.has_body = false;
+
+        let unit = self.options.unit;
+        let mut budget = overlap;
+        let mut kept = Vec::new();
+        for (page, text) in pieces.iter().rev() {
+            if budget == 0 || heading_level(text).is_some() {
+                break;
+            }
+            let len = unit.measure(text);
+            if len <= budget {
+                kept.push((*page, text.clone()));
+                budget = budget.saturating_sub(len + unit.measure("\n\n"));
+            } else {
+                kept.extend(tail(text, unit, budget).map(|t| (*page, t)));
+                break;
+            }
+        }
+        for (page, text) in kept.into_iter().rev() {
+            self.add(page, text);
+        }
+    }
+
+    fn emit(&mut self, pieces: &[(u32, String)]) {
+        let text = Self::text(pieces);
+        let page_start = pieces.iter().map(|(p, _)| *p).min().unwrap_or_default();
+        let page_end = pieces.iter().map(|(p, _)| *p).max().unwrap_or_default();
+        let images = self.doc.pages.iter()
+            .filter(|p| (page_start..=page_end).contains(&p.number))
+            .flat_map(|p| p.images.iter())
+            .filter(|path| text.contains(&format!("]({})", path)))
+            .cloned()
+            .collect();
+        self.chunks.push(Chunk {
+            source: self.doc.source.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            index: self.chunks.len(),
+            page_start,
+            page_end,
+            headings: self.headings.iter().map(|(_, t)| t.clone()).collect(),
+            text,
+            images,
+        });
+    }
+
+    fn push(&mut self, page: u32, text: String) {
+        let unit = self.options.unit;
+        let size = self.options.size;
+        if self.has_body && self.quarters_with(&text).div_ceil(4) > size {
+            // 重叠部分加上新内容不超过块大小
+            let room = size.saturating_sub(unit.measure(&text) + unit.measure("\n\n"));
+            self.flush(self.options.overlap.min(room));
+        }
+        self.add(page, text);
+        self.has_body = true;
+    }
+}
+
+pub fn chunk_document(doc: &SavedDocument, options: &ChunkOptions) -> Vec<Chunk> {
+    let options = ChunkOptions {
+        size: options.size.max(1),
+        overlap: options.overlap.min(options.size / 2),
+        ..*options
+    };
+    let mut builder = Builder { doc, options, chunks: Vec::new(), pieces: Vec::new(), quarters: 0, has_body: false, headings: Vec::new() };
+    for block in blocks(doc) {
+        if let Some(level) = block.heading {
+            // 标题处不保留重叠，块不跨越章节
+            builder.flush(0);
+            builder.headings.retain(|(l, _)| *l < level);
+            builder.headings.push((level, block.text.trim_start_matches('#').trim().to_string()));
+            builder.add(block.page, block.text);
+            continue;
+        }
+        for piece in split_block(&block.text, options.unit, options.size) {
+            builder.push(block.page, piece);
+        }
+    }
+    builder.flush(0);
+    builder.chunks
+}
+
+pub fn to_jsonl(chunks: &[Chunk]) -> anyhow::Result<String> {
+    let mut out = String::new();
+    for chunk in chunks {
+        out.push_str(&serde_json::to_string(chunk)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::SavedPage;
+
+    fn page(number: u32, markdown: &str, images: &[&str]) -> SavedPage {
+        SavedPage {
+            number,
+            markdown: markdown.into(),
+            images: images.iter().map(|i| i.to_string()).collect(),
+            provider: "mock".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn chunks_follow_headings_pages_and_overlap() {
+        let doc = SavedDocument {
+            source: "dir/report.pdf".into(),
+            provider: "mock".into(),
+            pages: vec![
+                page(1, "# Intro\n\naaaa bbbb\n\n## Scope\n\ncccc dddd", &[]),
+                page(2, "eeee ffff\n\n![f](images/p2.png)\n\n# Results\n\n```\ncode\n\nblock\n```", &["images/p2.png"]),
+            ],
+            ..Default::default()
+        };
+        let options = ChunkOptions { unit: ChunkUnit::Chars, size: 30, overlap: 10 };
+
+        let chunks = chunk_document(&doc, &options);
+
+        let texts: Vec<&str> = chunks.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, vec![
+            "# Intro\n\naaaa bbbb",
+            "## Scope\n\ncccc dddd\n\neeee ffff",
+            "eeee ffff\n\n![f](images/p2.png)",
+            "# Results\n\n```\ncode\n\nblock\n```",
+        ]);
+        assert_eq!(chunks[1].headings, vec!["Intro", "Scope"]);
+        assert_eq!((chunks[1].page_start, chunks[1].page_end), (1, 2));
+        assert_eq!(chunks[2].images, vec!["images/p2.png"]);
+        assert_eq!(chunks[3].headings, vec!["Results"]);
+        assert!(chunks.iter().all(|c| c.source == "report.pdf"));
+
+        // 超长段落在词的边界切分，相邻块带重叠
+        let long = split_block("one two three four five six", ChunkUnit::Chars, 10);
+        assert_eq!(long, vec!["one two", "three four", "five six"]);
+        assert_eq!(tail("alpha beta gamma", ChunkUnit::Chars, 8), Some("gamma".to_string()));
+        assert_eq!(ChunkUnit::Tokens.measure("你好abcd"), 3);
+
+        let jsonl = to_jsonl(&chunks).unwrap();
+        assert_eq!(jsonl.lines().count(), 4);
+        assert!(jsonl.starts_with("{\"source\":\"report.pdf\",\"index\":0,\"page_start\":1,\"page_end\":1,\"headings\":[\"Intro\"]"));
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::{BTreeMap, HashSet};
+use crate::export::SavedPage;
+
+// 只在每页开头和结尾的这几行 (非空行) 中查找页眉页脚
+const EDGE_LINES: usize = 3;
+// 至少出现在这个比例的页面中才视为页眉页脚
+const MIN_PAGE_RATIO: f32 = 0.5;
+// 页数太少时无法判断是否重复
+const MIN_PAGES: usize = 3;
+
+// 将被删除的一行
+#[derive(Debug, Clone, PartialEq)]
+pub struct Removal {
+    // 页面在文档中的索引
+    pub page: usize,
+    // 在该页 markdown 中的行号 (从 0 开始)
+    pub line: usize,
+    pub text: String,
+}
+
+// 比较用的形式：数字统一替换为 #，忽略大小写和多余空白，使 "Page 3 of 10" 与 "Page 4 of 10" 相同
+fn normalize(line: &str) -> String {
+    let mut out = String::new();
+    let mut last_digit = false;
+    for word in line.split_whitespace() {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        for c in word.chars().flat_map(char::to_lowercase) {
+            if c.is_ascii_digit() {
+                if !last_digit {
+                    out.push('#');
+                }
+                last_digit = true;
+            } else {
+                out.push(c);
+                last_digit = false;
+            }
+        }
+        last_digit = false;
+    }
+    out
+}
+
+// 图片、表格、标题、引用和代码块行不参与判断：它们的位置重复并不代表是页眉页脚
+// (如每页开头的 "# Chapter 1"、"# Chapter 2" 规范化后相同)
+fn is_candidate(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty()
+        && !trimmed.starts_with(['|', '#', '>'])
+        && !trimmed.starts_with("```")
+        && !trimmed.contains("](")
+}
+
+// 每页开头和结尾的候选行：(行号, 比较用的形式)
+fn edge_lines(markdown: &str) -> Vec<(usize, String)> {
+    let lines: Vec<(usize, &str)> = markdown.lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .collect();
+    let count = lines.len();
+    // 短页面只看第一行和最后一行，避免把正文当作页眉页脚
+    let edge = EDGE_LINES.min(count / 3);
+    lines.into_iter()
+        .enumerate()
+        .filter(|(pos, _)| *pos < edge || *pos + edge >= count)
+        .filter(|(_, (_, l))| is_candidate(l))
+        .map(|(_, (i, l))| (i, normalize(l)))
+        .collect()
+}
+
+// 找出在多数页面开头或结尾重复出现的行
+pub fn detect(pages: &[SavedPage]) -> Vec<Removal> {
+    if pages.len() < MIN_PAGES {
+        return Vec::new();
+    }
+    let edges: Vec<Vec<(usize, String)>> = pages.iter().map(|p| edge_lines(&p.markdown)).collect();
+
+    // 每种形式出现在多少页中 (同一页只计一次)
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for page_edges in &edges {
+        let unique: HashSet<&str> = page_edges.iter().map(|(_, key)| key.as_str()).collect();
+        for key in unique {
+            *counts.entry(key).or_default() += 1;
+        }
+    }
+    let threshold = ((pages.len() as f32 * MIN_PAGE_RATIO).ceil() as usize).max(MIN_PAGES);
+    let repeated: HashSet<&str> = counts.into_iter()
+        .filter(|(_, count)| *count >= threshold)
+        .map(|(key, _)| key)
+        .collect();
+
+    let mut removals = Vec::new();
+    for (page, page_edges) in edges.iter().enumerate() {
+        let lines: Vec<&str> = pages[page].markdown.lines().collect();
+        for (line, key) in page_edges {
+            if repeated.contains(key.as_str()) {
+                removals.push(Removal { page, line: *line, text: lines[*line].trim().to_string() });
+            }
+        }
+    }
+    removals
+}
+
+// 删除 detect 找出的行；页面内容在此之前不能被修改
+pub fn apply(pages: &mut [SavedPage], removals: &[Removal]) {
+    for (index, page) in pages.iter_mut().enumerate() {
+        let lines: HashSet<usize> = removals.iter().filter(|r| r.page == index).map(|r| r.line).collect();
+        if lines.is_empty() {
+            continue;
+        }
+        let kept: Vec<&str> = page.markdown.lines()
+            .enumerate()
+            .filter(|(i, _)| !lines.contains(i))
+            .map(|(_, l)| l)
+            .collect();
+        page.markdown = kept.join("\n").trim().to_string();
+    }
+}
+
+// 检测并删除页眉页脚，返回删除的行数
+pub fn strip_headers_footers(pages: &mut [SavedPage]) -> usize {
+    let removals = detect(pages);
+    apply(pages, &removals);
+    removals.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(number: u32, markdown: String) -> SavedPage {
+        SavedPage {
+            number,
+            markdown,
+            provider: "mock".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn repeated_headers_and_page_numbers_are_removed() {
+        let sections = ["Revenue", "Costs", "Outlook", ""];
+        let mut pages: Vec<SavedPage> = (1..=4)
+            .map(|n| page(n, format!(
+                "ACME Annual Report 2023\n\n# {}\n\n![img](images/page{n}_img-0.png)\n\nPage {n} of 4",
+                sections[n as usize - 1]
+            )))
+            .collect();
+        pages[3].markdown = "Body only on the last page.".to_string();
+
+        let removals = detect(&pages);
+
+        // 页眉和页码在 3/4 的页面中出现；图片行和各页不同的正文保留
+        assert_eq!(removals.iter().filter(|r| r.page == 0).map(|r| r.text.as_str()).collect::<Vec<_>>(),
+            vec!["ACME Annual Report 2023", "Page 1 of 4"]);
+        assert!(removals.iter().all(|r| r.page != 3));
+
+        apply(&mut pages, &removals);
+        assert_eq!(pages[1].markdown, "# Costs\n\n![img](images/page2_img-0.png)");
+        assert_eq!(pages[3].markdown, "Body only on the last page.");
+
+        let mut short = vec![page(1, "Header\n\na".into()), page(2, "Header\n\nb".into())];
+        assert_eq!(strip_headers_footers(&mut short), 0);
+    }
+
+    #[test]
+    fn body_lines_of_short_pages_are_kept() {
+        let mut pages: Vec<SavedPage> = (1..=4)
+            .map(|n| page(n, format!("ACME Report\n\nSee the appendix for details.\n\nPage {n}")))
+            .collect();
+
+        assert_eq!(strip_headers_footers(&mut pages), 8);
+        assert!(pages.iter().all(|p| p.markdown == "See the appendix for details."));
+    }
+
+    #[test]
+    fn numbered_chapter_headings_are_kept() {
+        let mut pages: Vec<SavedPage> = (1..=6)
+            .map(|n| page(n, format!("# Chapter {n}\n\nFirst line {n}.\n\nSecond line {n}.\n\nThird line {n}.\n\n- {n} -")))
+            .collect();
+
+        assert_eq!(strip_headers_footers(&mut pages), 6);
+        assert!(pages[4].markdown.starts_with("# Chapter 5\n\n"));
+        assert!(pages[4].markdown.ends_with("Third line 5."));
+    }
+}
@@ -0,0 +1,329 @@
+use anyhow::Result;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use crate::export::{SavedDocument, escape_html};
+use crate::config::OutputOptions;
+use crate::history::JobOptions;
+use crate::layout::OutputLayout;
+use crate::i18n::I18n;
+use crate::pipeline::{self, ProgressUpdate};
+use crate::providers::{OcrOptions, OcrProvider};
+
+pub const REPORT_JSON: &str = "comparison.json";
+pub const REPORT_HTML: &str = "comparison.html";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffTag {
+    Equal,
+    // 仅出现在左侧 (A) 的行
+    Removed,
+    // 仅出现在右侧 (B) 的行
+    Added,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DiffLine {
+    pub tag: DiffTag,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PageComparison {
+    pub number: u32,
+    // 0.0 - 1.0，基于字符级 diff
+    pub similarity: f32,
+    pub diff: Vec<DiffLine>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ComparisonReport {
+    pub source: PathBuf,
+    pub left_label: String,
+    pub right_label: String,
+    pub left_dir: PathBuf,
+    pub right_dir: PathBuf,
+    pub similarity: f32,
+    pub pages: Vec<PageComparison>,
+}
+
+impl ComparisonReport {
+    pub fn load(compare_dir: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(compare_dir.join(REPORT_JSON))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+// 参与比较的一侧：供应商及其显示名 (如 "mistral / mistral-ocr-latest")
+pub struct CompareSide {
+    pub provider: Box<dyn OcrProvider>,
+    pub label: String,
+}
+
+// 用两个供应商分别处理同一文档，结果保存在 <output>/compare_<stem>/A_<stem> 和 B_<stem>
+pub async fn run_comparison(
+    left: CompareSide,
+    right: CompareSide,
+    input: &Path,
+    output_base: &Path,
+    tx: &mpsc::Sender<ProgressUpdate>,
+) -> Result<PathBuf> {
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    let compare_dir = output_base.join(format!("compare_{}", stem));
+
+    // 只需要 result.json，不导出其他格式；两侧的目录固定，不受用户的布局设置影响
+    let job = |prefix: &str| JobOptions {
+        output_base: compare_dir.clone(),
+        output: OutputOptions {
+            export_formats: Vec::new(),
+            layout: OutputLayout {
+                path_template: format!("{}{{stem}}/complete.md", prefix),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    };
+
+    let _ = tx.send(ProgressUpdate::Message(format!("A: {}", left.label))).await;
+    let left_dir = pipeline::process_single_file(Arc::new(left.provider), input, &job("A_"), &OcrOptions::default(), tx).await?;
+    let _ = tx.send(ProgressUpdate::Total(0.5)).await;
+
+    let _ = tx.send(ProgressUpdate::Message(format!("B: {}", right.label))).await;
+    let right_dir = pipeline::process_single_file(Arc::new(right.provider), input, &job("B_"), &OcrOptions::default(), tx).await?;
+
+    let report = compare_documents(
+        &SavedDocument::load(&left_dir)?,
+        &SavedDocument::load(&right_dir)?,
+        left.label,
+        right.label,
+        left_dir,
+        right_dir,
+    );
+    std::fs::write(compare_dir.join(REPORT_JSON), serde_json::to_string_pretty(&report)?)?;
+    std::fs::write(compare_dir.join(REPORT_HTML), render_html_report(&report))?;
+    Ok(compare_dir)
+}
+
+pub fn compare_documents(
+    left: &SavedDocument,
+    right: &SavedDocument,
+    left_label: String,
+    right_label: String,
+    left_dir: PathBuf,
+    right_dir: PathBuf,
+) -> ComparisonReport {
+    // 按页码对齐，缺失的页面视为空
+    let mut numbers: Vec<u32> = left.pages.iter().chain(right.pages.iter()).map(|p| p.number).collect();
+    numbers.sort_unstable();
+    numbers.dedup();
+
+    let page_text = |doc: &SavedDocument, number: u32| {
+        doc.pages.iter().find(|p| p.number == number).map(|p| p.markdown.clone()).unwrap_or_default()
+    };
+
+    let pages: Vec<PageComparison> = numbers.into_iter()
+        .map(|number| compare_page(number, &page_text(left, number), &page_text(right, number)))
+        .collect();
+
+    let similarity = if pages.is_empty() {
+        1.0
+    } else {
+        pages.iter().map(|p| p.similarity).sum::<f32>() / pages.len() as f32
+    };
+
+    ComparisonReport {
+        source: left.source.clone(),
+        left_label,
+        right_label,
+        left_dir,
+        right_dir,
+        similarity,
+        pages,
+    }
+}
+
+fn compare_page(number: u32, left: &str, right: &str) -> PageComparison {
+    let similarity = TextDiff::configure()
+        .timeout(Duration::from_secs(1))
+        .diff_chars(left, right)
+        .ratio();
+
+    let diff = TextDiff::from_lines(left, right)
+        .iter_all_changes()
+        .map(|change| DiffLine {
+            tag: match change.tag() {
+                ChangeTag::Equal => DiffTag::Equal,
+                ChangeTag::Delete => DiffTag::Removed,
+                ChangeTag::Insert => DiffTag::Added,
+            },
+            text: change.value().trim_end_matches('\n').to_string(),
+        })
+        .collect();
+
+    PageComparison { number, similarity, diff }
+}
+
+pub fn render_html_report(report: &ComparisonReport) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n", escape_html(&report.source.to_string_lossy())));
+    html.push_str("<style>\nbody{font-family:sans-serif;margin:2em}\ntable{border-collapse:collapse;width:100%;table-layout:fixed}\n\
+td,th{border:1px solid #ccc;vertical-align:top;padding:4px}\npre{white-space:pre-wrap;margin:0}\n\
+.del{background:#fdd}\n.ins{background:#dfd}\n</style>\n</head>\n<body>\n");
+    html.push_str(&format!("<h1>{}</h1>\n", escape_html(&report.source.to_string_lossy())));
+    html.push_str(&format!(
+        "<p>A: {} &nbsp; B: {} &nbsp; Similarity: {:.1}%</p>\n",
+        escape_html(&report.left_label),
+        escape_html(&report.right_label),
+        report.similarity * 100.0
+    ));
+
+    for page in &report.pages {
+        html.push_str(&format!("<h2>Page {} ({:.1}%)</h2>\n<table>\n<tr><th>A</th><th>B</th></tr>\n<tr><td>", page.number, page.similarity * 100.0));
+        for line in page.diff.iter().filter(|l| l.tag != DiffTag::Added) {
+            html.push_str(&diff_line_html(line));
+        }
+        html.push_str("</td><td>");
+        for line in page.diff.iter().filter(|l| l.tag != DiffTag::Removed) {
+            html.push_str(&diff_line_html(line));
+        }
+        html.push_str("</td></tr>\n</table>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn diff_line_html(line: &DiffLine) -> String {
+    let class = match line.tag {
+        DiffTag::Equal => "",
+        DiffTag::Removed => " class=\"del\"",
+        DiffTag::Added => " class=\"ins\"",
+    };
+    format!("<pre{}>{}</pre>", class, escape_html(&line.text))
+}
+
+// 比较结果窗口：逐页左右对照显示差异
+pub struct CompareViewer {
+    compare_dir: PathBuf,
+    report: Result<ComparisonReport, String>,
+    page_index: usize,
+}
+
+impl CompareViewer {
+    pub fn new(compare_dir: PathBuf) -> Self {
+        let report = ComparisonReport::load(&compare_dir).map_err(|e| e.to_string());
+        Self {
+            compare_dir,
+            report,
+            page_index: 0,
+        }
+    }
+
+    // 返回 false 表示窗口已被关闭
+    pub fn show(&mut self, ctx: &egui::Context, i18n: &I18n) -> bool {
+        let mut open = true;
+        egui::Window::new(i18n.t("compare_title"))
+            .id(egui::Id::new("compare_viewer"))
+            .open(&mut open)
+            .default_size([900.0, 700.0])
+            .show(ctx, |ui| {
+                let report = match &self.report {
+                    Ok(report) => report,
+                    Err(e) => {
+                        ui.colored_label(ui.visuals().error_fg_color, e);
+                        return;
+                    }
+                };
+
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}: {:.1}%", i18n.t("similarity"), report.similarity * 100.0));
+                    ui.separator();
+                    let page_count = report.pages.len();
+                    if page_count > 0 {
+                        if ui.add_enabled(self.page_index > 0, egui::Button::new("◀")).clicked() {
+                            self.page_index -= 1;
+                        }
+                        ui.label(format!("{} {} / {}", i18n.t("page"), self.page_index + 1, page_count));
+                        if ui.add_enabled(self.page_index + 1 < page_count, egui::Button::new("▶")).clicked() {
+                            self.page_index += 1;
+                        }
+                    }
+                    ui.separator();
+                    if ui.button(i18n.t("open_report")).clicked() {
+                        let _ = open::that(self.compare_dir.join(REPORT_HTML));
+                    }
+                    if ui.button("A 📁").clicked() {
+                        let _ = open::that(&report.left_dir);
+                    }
+                    if ui.button("B 📁").clicked() {
+                        let _ = open::that(&report.right_dir);
+                    }
+                });
+                ui.separator();
+
+                let Some(page) = report.pages.get(self.page_index) else { return };
+                ui.label(format!("{} {}: {:.1}%", i18n.t("page"), page.number, page.similarity * 100.0));
+
+                let removed = egui::Color32::from_rgba_unmultiplied(255, 80, 80, 60);
+                let added = egui::Color32::from_rgba_unmultiplied(80, 200, 80, 60);
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ui.columns(2, |columns| {
+                        columns[0].strong(format!("A: {}", report.left_label));
+                        columns[1].strong(format!("B: {}", report.right_label));
+                        for line in &page.diff {
+                            let text = egui::RichText::new(&line.text).monospace();
+                            match line.tag {
+                                DiffTag::Equal => {
+                                    columns[0].label(text.clone());
+                                    columns[1].label(text);
+                                }
+                                DiffTag::Removed => { columns[0].label(text.background_color(removed)); }
+                                DiffTag::Added => { columns[1].label(text.background_color(added)); }
+                            }
+                        }
+                    });
+                });
+            });
+        open
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::SavedPage;
+
+    fn doc(pages: &[&str]) -> SavedDocument {
+        SavedDocument {
+            source: PathBuf::from("doc.pdf"),
+            provider: "mock".into(),
+            pages: pages.iter().enumerate().map(|(i, md)| SavedPage {
+                number: i as u32 + 1,
+                markdown: md.to_string(),
+                provider: "mock".into(),
+                ..Default::default()
+            }).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn pages_are_aligned_and_scored() {
+        let left = doc(&["same\ntext", "hello world"]);
+        let right = doc(&["same\ntext", "hello there", "extra"]);
+
+        let report = compare_documents(&left, &right, "A".into(), "B".into(), PathBuf::new(), PathBuf::new());
+
+        assert_eq!(report.pages.len(), 3);
+        assert_eq!(report.pages[0].similarity, 1.0);
+        assert!(report.pages[1].similarity > 0.3 && report.pages[1].similarity < 1.0);
+        assert_eq!(report.pages[2].similarity, 0.0);
+        assert!(report.pages[1].diff.iter().any(|l| l.tag == DiffTag::Removed && l.text == "hello world"));
+        assert!(render_html_report(&report).contains("<pre class=\"ins\">hello there</pre>"));
+    }
+}
@@ -1,5 +1,14 @@
 use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
 use std::collections::HashMap;
+use std::path::PathBuf;
+use crate::bundle::BundleMode;
+use crate::chunks::ChunkOptions;
+use crate::export::{ExportFormat, PageMarker};
+use crate::images::ImageExport;
+use crate::layout::OutputLayout;
+use crate::metadata::FrontMatterOptions;
+use crate::providers::registry::SettingsMap;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AppConfig {
@@ -8,9 +17,17 @@ pub struct AppConfig {
     
     pub language: String,
     
-    // New fields
     pub active_provider: String,
+    // Deprecated, migrated into provider_settings
     pub api_keys: HashMap<String, String>,
+
+    // provider id -> (field key -> value), see providers::registry
+    #[serde(default)]
+    pub provider_settings: SettingsMap,
+
+    // 输出设置，与任务记录中的字段相同 (平铺在配置文件中)
+    #[serde(flatten)]
+    pub output: OutputOptions,
 }
 
 impl Default for AppConfig {
@@ -20,6 +37,74 @@ impl Default for AppConfig {
             language: "zh_CN".to_string(),
             active_provider: "mistral".to_string(),
             api_keys: HashMap::new(),
+            provider_settings: SettingsMap::new(),
+            output: OutputOptions::default(),
+        }
+    }
+}
+
+// 识别结果的输出设置：保存在配置中，并随每个任务记录，重新运行时原样使用
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OutputOptions {
+    #[serde(default = "default_export_formats")]
+    pub export_formats: Vec<ExportFormat>,
+    // 结构化提取使用的 JSON Schema 文件，未设置时不提取
+    #[serde(default)]
+    pub annotation_schema: Option<PathBuf>,
+    // 提取出的图片的保存格式
+    #[serde(default)]
+    pub image_export: ImageExport,
+    // 输出路径模板、图片位置和目录冲突的处理方式
+    #[serde(default, alias = "output_layout")]
+    pub layout: OutputLayout,
+    // 在 debug/ 中保留每个分块的 Markdown，便于排查拆分处理的问题
+    #[serde(default)]
+    pub keep_chunk_files: bool,
+    // 识别完成后把结果打包为 zip
+    #[serde(default)]
+    pub bundle: BundleMode,
+    // Markdown 开头的 YAML front matter 及自定义字段
+    #[serde(default)]
+    pub front_matter: FrontMatterOptions,
+    // 合并输出中每页开头的标记
+    #[serde(default)]
+    pub page_marker: PageMarker,
+    // 删除各页重复出现的页眉、页脚和页码
+    #[serde(default)]
+    pub strip_headers: bool,
+    // 拼接跨页的段落、被连字符拆开的单词和表格
+    #[serde(default)]
+    pub stitch_pages: bool,
+    // 在 Markdown 和 HTML 开头插入目录，可选以 PDF 书签为目录
+    #[serde(default)]
+    pub toc: bool,
+    #[serde(default)]
+    pub toc_from_outline: bool,
+    // 分块 JSONL 导出的单位、大小和重叠
+    #[serde(default)]
+    pub chunking: ChunkOptions,
+}
+
+fn default_export_formats() -> Vec<ExportFormat> {
+    vec![ExportFormat::Markdown]
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        Self {
+            export_formats: default_export_formats(),
+            annotation_schema: None,
+            image_export: ImageExport::default(),
+            layout: OutputLayout::default(),
+            keep_chunk_files: false,
+            bundle: BundleMode::default(),
+            front_matter: FrontMatterOptions::default(),
+            page_marker: PageMarker::default(),
+            strip_headers: false,
+            stitch_pages: false,
+            toc: false,
+            toc_from_outline: false,
+            chunking: ChunkOptions::default(),
         }
     }
 }
@@ -34,6 +119,12 @@ pub fn load_config() -> AppConfig {
         }
     }
     
+    // Migration: move per-provider keys into the provider settings
+    for (provider, key) in &config.api_keys {
+        let values = config.provider_settings.entry(provider.clone()).or_default();
+        values.entry("api_key".to_string()).or_insert_with(|| key.clone());
+    }
+    
     // Ensure default active provider
     if config.active_provider.is_empty() {
         config.active_provider = "mistral".to_string();
@@ -45,4 +136,57 @@ pub fn load_config() -> AppConfig {
 pub fn save_config(config: &AppConfig) -> anyhow::Result<()> {
     confy::store("ocr-eg", None, config)?;
     Ok(())
-}
\ No newline at end of file
+}
+
+// 历史记录、批量任务等与配置文件放在同一目录，使用 JSON 以便保存嵌套结构
+fn json_path(name: &str) -> anyhow::Result<PathBuf> {
+    let path = confy::get_configuration_file_path("ocr-eg", name)?;
+    Ok(path.with_extension("json"))
+}
+
+// 文件不存在或无法解析时返回默认值
+pub fn load_json<T: DeserializeOwned + Default>(name: &str) -> T {
+    json_path(name)
+        .ok()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_json<T: Serialize>(name: &str, value: &T) -> anyhow::Result<()> {
+    let path = json_path(name)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(value)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{ExportFormat, PageMarker};
+
+    #[test]
+    fn output_settings_keep_their_flat_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let mut config = AppConfig::default();
+        config.output.export_formats.push(ExportFormat::Html);
+        config.output.page_marker = PageMarker::Custom("[p{page}]".into());
+        config.output.layout.path_template = "{stem}/{stem}.md".into();
+        confy::store_path(&path, &config).unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains("export_formats = [") && text.contains("[layout]"));
+        let loaded: AppConfig = confy::load_path(&path).unwrap();
+        assert_eq!(loaded.output.export_formats, config.output.export_formats);
+        assert_eq!(loaded.output.page_marker, config.output.page_marker);
+
+        // 旧版本的配置文件中输出布局的键名为 output_layout
+        let old = text.replace("[layout]", "[output_layout]");
+        std::fs::write(&path, old).unwrap();
+        let loaded: AppConfig = confy::load_path(&path).unwrap();
+        assert_eq!(loaded.output.layout.path_template, "{stem}/{stem}.md");
+    }
+}
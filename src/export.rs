@@ -0,0 +1,469 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use pulldown_cmark::{Event, Tag, TagEnd};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use crate::chunks::{self, ChunkOptions};
+use crate::metadata::FrontMatter;
+use crate::providers::{BoundingBox, PageDimensions};
+use crate::stitch;
+use crate::tables;
+use crate::toc::{self, OutlineEntry};
+
+// 每个输出目录中缓存的识别结果，用于重新导出
+pub const DOCUMENT_FILE: &str = "result.json";
+// 结构化提取的结果 (仅在设置了 Schema 时生成)
+pub const ANNOTATIONS_FILE: &str = "annotations.json";
+// 各分块的中间 Markdown (仅在开启保留时生成)
+pub const DEBUG_DIR: &str = "debug";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    // 图片以 data URI 内嵌的单个 Markdown 文件
+    EmbeddedMarkdown,
+    // 页面中的表格，每个表格一个 CSV 文件
+    TablesCsv,
+    // 页面中的表格，一个工作簿，每个表格一个工作表
+    TablesXlsx,
+    // 供检索使用的分块 JSONL
+    Chunks,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 6] = [
+        ExportFormat::Markdown,
+        ExportFormat::Html,
+        ExportFormat::EmbeddedMarkdown,
+        ExportFormat::TablesCsv,
+        ExportFormat::TablesXlsx,
+        ExportFormat::Chunks,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "Markdown",
+            ExportFormat::Html => "HTML",
+            ExportFormat::EmbeddedMarkdown => "Markdown (embedded images)",
+            ExportFormat::TablesCsv => "Tables (CSV)",
+            ExportFormat::TablesXlsx => "Tables (XLSX)",
+            ExportFormat::Chunks => "Chunks (JSONL)",
+        }
+    }
+
+    pub fn file_name(&self, output_name: &str) -> String {
+        match self {
+            ExportFormat::Markdown => format!("{}.md", output_name),
+            ExportFormat::Html => format!("{}.html", output_name),
+            ExportFormat::EmbeddedMarkdown => format!("{}.embedded.md", output_name),
+            // CSV 文件写入该目录
+            ExportFormat::TablesCsv => tables::TABLES_DIR.to_string(),
+            ExportFormat::TablesXlsx => format!("{}_tables.xlsx", output_name),
+            ExportFormat::Chunks => format!("{}.chunks.jsonl", output_name),
+        }
+    }
+}
+
+// 合并输出中每页开头的标记
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub enum PageMarker {
+    None,
+    // ## Page N
+    #[default]
+    Heading,
+    // <!-- page N -->
+    Comment,
+    // 页与页之间的水平线
+    Rule,
+    // 自定义模板，{page} 替换为页码
+    Custom(String),
+}
+
+impl PageMarker {
+    // 切换到自定义标记时预填的模板
+    pub const DEFAULT_CUSTOM_TEMPLATE: &'static str = "[[page {page}]]";
+
+    // i18n key
+    pub fn label(&self) -> &'static str {
+        match self {
+            PageMarker::None => "marker_none",
+            PageMarker::Heading => "marker_heading",
+            PageMarker::Comment => "marker_comment",
+            PageMarker::Rule => "marker_rule",
+            PageMarker::Custom(_) => "marker_custom",
+        }
+    }
+
+    pub fn render(&self, number: u32, first: bool) -> Option<String> {
+        match self {
+            PageMarker::None => None,
+            PageMarker::Heading => Some(format!("## Page {}", number)),
+            PageMarker::Comment => Some(format!("<!-- page {} -->", number)),
+            PageMarker::Rule if first => None,
+            PageMarker::Rule => Some("---".to_string()),
+            PageMarker::Custom(template) => Some(template.replace("{page}", &number.to_string())),
+        }
+    }
+}
+
+// 已保存的页面：图片已写入磁盘，markdown 中的图片链接已改写为相对路径
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SavedPage {
+    // 文档中的页码 (从 1 开始)
+    pub number: u32,
+    pub markdown: String,
+    pub images: Vec<String>,
+    // 产生该页结果的供应商
+    #[serde(default)]
+    pub provider: String,
+    // 供应商返回的页面尺寸与 DPI，图片位置以此为坐标系
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<PageDimensions>,
+    // 图片路径 -> 在页面中的位置
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub image_boxes: BTreeMap<String, BoundingBox>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SavedDocument {
+    pub source: PathBuf,
+    pub provider: String,
+    pub pages: Vec<SavedPage>,
+    // 导出文件名 (不含扩展名)，由输出路径模板决定
+    #[serde(default = "default_output_name")]
+    pub output_name: String,
+    // 开启时写在 Markdown 开头的 YAML front matter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub front_matter: Option<FrontMatter>,
+    #[serde(default)]
+    pub page_marker: PageMarker,
+    // 拼接跨页的段落和表格
+    #[serde(default)]
+    pub stitch_pages: bool,
+    // 在正文前插入由标题生成的目录
+    #[serde(default)]
+    pub toc: bool,
+    // 源 PDF 的书签，开启目录时用作目录条目
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outline: Vec<OutlineEntry>,
+    // 分块导出的大小和重叠
+    #[serde(default)]
+    pub chunking: ChunkOptions,
+}
+
+fn default_output_name() -> String {
+    "complete".to_string()
+}
+
+impl Default for SavedDocument {
+    fn default() -> Self {
+        Self {
+            source: PathBuf::new(),
+            provider: String::new(),
+            pages: Vec::new(),
+            output_name: default_output_name(),
+            front_matter: None,
+            page_marker: PageMarker::default(),
+            stitch_pages: false,
+            toc: false,
+            outline: Vec::new(),
+            chunking: ChunkOptions::default(),
+        }
+    }
+}
+
+impl SavedDocument {
+    pub fn load(out_dir: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(out_dir.join(DOCUMENT_FILE))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self, out_dir: &Path) -> Result<()> {
+        std::fs::write(out_dir.join(DOCUMENT_FILE), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let front_matter = self.front_matter.as_ref()
+            .map(|f| f.render(self.pages.len()))
+            .unwrap_or_default();
+        front_matter + &self.body_with_toc()
+    }
+
+    // 正文，开启时前面加上目录
+    fn body_with_toc(&self) -> String {
+        let body = render_pages(&self.pages, &self.page_marker, self.stitch_pages);
+        if !self.toc {
+            return body;
+        }
+        toc::render(&toc::build(self, &body)) + &body
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DocumentAnnotations {
+    pub source: PathBuf,
+    // 文档级提取结果；大文件拆分处理时每个分块一个
+    pub document: Vec<serde_json::Value>,
+    // 图片路径 (相对于输出目录) -> 该区域的提取结果
+    pub images: BTreeMap<String, serde_json::Value>,
+}
+
+impl DocumentAnnotations {
+    pub fn is_empty(&self) -> bool {
+        self.document.is_empty() && self.images.is_empty()
+    }
+
+    pub fn load(out_dir: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(out_dir.join(ANNOTATIONS_FILE))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self, out_dir: &Path) -> Result<()> {
+        std::fs::write(out_dir.join(ANNOTATIONS_FILE), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+// 将多个文档的文档级提取结果汇总为 CSV：每个结果一行，顶层字段为列，嵌套值写为 JSON
+pub fn write_annotations_csv(path: &Path, documents: &[DocumentAnnotations]) -> Result<()> {
+    let mut columns: Vec<String> = Vec::new();
+    for value in documents.iter().flat_map(|d| d.document.iter()) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for key in map.keys() {
+                    if !columns.contains(key) {
+                        columns.push(key.clone());
+                    }
+                }
+            }
+            _ if !columns.iter().any(|c| c == "value") => columns.push("value".to_string()),
+            _ => {}
+        }
+    }
+
+    let mut csv = String::new();
+    let header: Vec<String> = std::iter::once("source".to_string()).chain(columns.iter().cloned()).collect();
+    csv.push_str(&csv_row(&header));
+    for doc in documents {
+        for value in &doc.document {
+            let mut row = vec![doc.source.to_string_lossy().to_string()];
+            for column in &columns {
+                let cell = match value {
+                    serde_json::Value::Object(map) => map.get(column),
+                    other if column == "value" => Some(other),
+                    _ => None,
+                };
+                row.push(match cell {
+                    None | Some(serde_json::Value::Null) => String::new(),
+                    Some(serde_json::Value::String(s)) => s.clone(),
+                    Some(other) => other.to_string(),
+                });
+            }
+            csv.push_str(&csv_row(&row));
+        }
+    }
+    std::fs::write(path, csv)?;
+    Ok(())
+}
+
+pub fn csv_row(cells: &[String]) -> String {
+    let cells: Vec<String> = cells.iter()
+        .map(|c| {
+            if c.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", c.replace('"', "\"\""))
+            } else {
+                c.clone()
+            }
+        })
+        .collect();
+    format!("{}\r\n", cells.join(","))
+}
+
+// stitch 为 true 时，跨页延续的段落和表格会被拼接，该页的标记改为行内注释锚点
+pub fn render_pages(pages: &[SavedPage], marker: &PageMarker, stitch: bool) -> String {
+    let mut out = String::new();
+    for (i, page) in pages.iter().enumerate() {
+        let page_marker = marker.render(page.number, i == 0);
+        if i > 0 && stitch {
+            let anchor = match marker {
+                PageMarker::None => String::new(),
+                _ => format!("<!-- page {} -->", page.number),
+            };
+            if stitch::join(&mut out, &page.markdown, &anchor) {
+                continue;
+            }
+        }
+        if i > 0 {
+            out.push_str("\n\n");
+        }
+        if let Some(page_marker) = page_marker {
+            out.push_str(&page_marker);
+            out.push_str("\n\n");
+        }
+        out.push_str(&page.markdown);
+    }
+    out
+}
+
+pub fn export_document(doc: &SavedDocument, out_dir: &Path, formats: &[ExportFormat]) -> Result<Vec<PathBuf>> {
+    let mut written = Vec::new();
+    for format in formats {
+        let path = out_dir.join(format.file_name(&doc.output_name));
+        let content = match format {
+            ExportFormat::Markdown => doc.to_markdown(),
+            ExportFormat::Html => render_html(doc),
+            ExportFormat::EmbeddedMarkdown => embed_images(doc, out_dir),
+            // 没有表格的文档不生成表格文件
+            ExportFormat::TablesCsv => {
+                written.extend(tables::write_csv(&tables::extract_tables(&doc.pages), out_dir)?);
+                continue;
+            }
+            ExportFormat::TablesXlsx => {
+                if tables::write_xlsx(&tables::extract_tables(&doc.pages), &path)? {
+                    written.push(path);
+                }
+                continue;
+            }
+            ExportFormat::Chunks => chunks::to_jsonl(&chunks::chunk_document(doc, &doc.chunking))?,
+        };
+        std::fs::write(&path, content)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+// 把图片链接替换为 data URI；读取失败的图片保留原链接
+fn embed_images(doc: &SavedDocument, out_dir: &Path) -> String {
+    let mut markdown = doc.to_markdown();
+    for path in doc.pages.iter().flat_map(|p| p.images.iter()) {
+        if let Ok(uri) = crate::images::data_uri(&out_dir.join(path)) {
+            markdown = markdown.replace(&format!("]({})", path), &format!("]({})", uri));
+        }
+    }
+    markdown
+}
+
+// 图片在原页面中所占的宽度比例 (%)；去重后的图片出现在多页时以第一次出现为准
+fn image_widths(doc: &SavedDocument) -> HashMap<&str, f32> {
+    let mut widths = HashMap::new();
+    for page in &doc.pages {
+        let Some(dimensions) = &page.dimensions else { continue };
+        for (path, bbox) in &page.image_boxes {
+            widths.entry(path.as_str()).or_insert(bbox.width_ratio(dimensions) * 100.0);
+        }
+    }
+    widths
+}
+
+// 有位置信息的图片单独渲染为 <img>，再加上宽度；按解析后的链接目标匹配
+fn with_image_widths<'a>(events: Vec<Event<'a>>, widths: &HashMap<&str, f32>) -> Vec<Event<'a>> {
+    let mut out = Vec::with_capacity(events.len());
+    // (宽度, 图片的事件, 嵌套深度)
+    let mut image: Option<(f32, Vec<Event<'a>>, usize)> = None;
+    for event in events {
+        if let Some((width, inner, depth)) = &mut image {
+            match &event {
+                Event::Start(Tag::Image { .. }) => *depth += 1,
+                Event::End(TagEnd::Image) => *depth -= 1,
+                _ => {}
+            }
+            inner.push(event);
+            if *depth == 0 {
+                let mut html = String::new();
+                pulldown_cmark::html::push_html(&mut html, inner.drain(..));
+                let tag = html.trim_end().trim_end_matches("/>").trim_end();
+                out.push(Event::InlineHtml(format!("{} style=\"width:{:.1}%\" />", tag, width).into()));
+                image = None;
+            }
+            continue;
+        }
+        if let Event::Start(Tag::Image { dest_url, .. }) = &event {
+            if let Some(width) = widths.get(dest_url.as_ref()) {
+                image = Some((*width, vec![event], 1));
+                continue;
+            }
+        }
+        out.push(event);
+    }
+    out
+}
+
+fn render_html(doc: &SavedDocument) -> String {
+    let markdown = doc.body_with_toc();
+    let parser = pulldown_cmark::Parser::new_ext(&markdown, pulldown_cmark::Options::ENABLE_TABLES);
+    let mut events: Vec<Event> = parser.collect();
+    if doc.toc {
+        // 标题带上 id，目录链接才能跳转
+        events = toc::with_heading_ids(events);
+    }
+    // 按图片在原页面中所占的宽度比例显示
+    let events = with_image_widths(events, &image_widths(doc));
+    let mut body = String::new();
+    pulldown_cmark::html::push_html(&mut body, events.into_iter());
+
+    let title = doc.source.file_stem().unwrap_or_default().to_string_lossy();
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(&title),
+        body
+    )
+}
+
+// 也用于属性值，因此转义引号
+pub(crate) fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(number: u32, markdown: &str) -> SavedPage {
+        SavedPage {
+            number,
+            markdown: markdown.into(),
+            provider: "mock".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn page_markers_are_configurable() {
+        let pages = vec![page(3, "a"), page(4, "b")];
+
+        assert_eq!(render_pages(&pages, &PageMarker::Heading, false), "## Page 3\n\na\n\n## Page 4\n\nb");
+        assert_eq!(render_pages(&pages, &PageMarker::None, false), "a\n\nb");
+        assert_eq!(render_pages(&pages, &PageMarker::Comment, false), "<!-- page 3 -->\n\na\n\n<!-- page 4 -->\n\nb");
+        assert_eq!(render_pages(&pages, &PageMarker::Rule, false), "a\n\n---\n\nb");
+        assert_eq!(render_pages(&pages, &PageMarker::Custom("[p{page}]".into()), false), "[p3]\n\na\n\n[p4]\n\nb");
+
+        let continued = vec![page(3, "The quick"), page(4, "brown fox."), page(5, "New page.")];
+        assert_eq!(render_pages(&continued, &PageMarker::Heading, true),
+            "## Page 3\n\nThe quick<!-- page 4 --> brown fox.\n\n## Page 5\n\nNew page.");
+    }
+
+    #[test]
+    fn image_widths_are_set_once_per_image() {
+        use crate::providers::{BoundingBox, PageDimensions};
+
+        let dimensions = Some(PageDimensions { dpi: 200, height: 1000, width: 1000 });
+        let bbox = |right| BoundingBox { top_left_x: 0, top_left_y: 0, bottom_right_x: right, bottom_right_y: 10 };
+        let mut first = page(1, "![a](images/shared.png)\n\n![b](<images/my scan.png>)");
+        first.dimensions = dimensions;
+        first.image_boxes.insert("images/shared.png".into(), bbox(500));
+        first.image_boxes.insert("images/my scan.png".into(), bbox(250));
+        let mut second = page(2, "![a](images/shared.png)");
+        second.dimensions = dimensions;
+        second.image_boxes.insert("images/shared.png".into(), bbox(800));
+        let doc = SavedDocument { source: "a\"b.pdf".into(), pages: vec![first, second], ..Default::default() };
+
+        let html = render_html(&doc);
+
+        assert_eq!(html.matches("<img src=\"images/shared.png\" alt=\"a\" style=\"width:50.0%\" />").count(), 2);
+        assert!(html.contains("<img src=\"images/my%20scan.png\" alt=\"b\" style=\"width:25.0%\" />"));
+        assert_eq!(html.matches("style=").count(), 3);
+        assert!(html.contains("<title>a&quot;b</title>"));
+    }
+}
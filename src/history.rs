@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use crate::config::{OutputOptions, load_json, save_json};
+
+// 历史记录最多保留的任务数，超出后丢弃最旧的
+const MAX_JOBS: usize = 200;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Completed,
+    PartiallyFailed,
+    Failed,
+    // 被用户取消；未处理的文件记为失败，可从历史记录中重新运行
+    Cancelled,
+}
+
+// 单个输入文件的处理结果
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FileOutcome {
+    pub input: PathBuf,
+    pub output_dir: Option<PathBuf>,
+    pub error: Option<String>,
+}
+
+// 任务启动时使用的选项，用于重新运行
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct JobOptions {
+    pub output_base: PathBuf,
+    #[serde(flatten)]
+    pub output: OutputOptions,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JobRecord {
+    pub id: String,
+    // Unix timestamps (seconds)
+    pub started_at: i64,
+    pub finished_at: i64,
+    pub provider: String,
+    pub options: JobOptions,
+    pub files: Vec<FileOutcome>,
+    pub status: JobStatus,
+}
+
+impl JobRecord {
+    pub fn new(provider: String, options: JobOptions, started_at: i64, files: Vec<FileOutcome>) -> Self {
+        let failed = files.iter().filter(|f| f.error.is_some()).count();
+        let status = if failed == 0 {
+            JobStatus::Completed
+        } else if failed == files.len() {
+            JobStatus::Failed
+        } else {
+            JobStatus::PartiallyFailed
+        };
+
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            started_at,
+            finished_at: chrono::Local::now().timestamp(),
+            provider,
+            options,
+            files,
+            status,
+        }
+    }
+
+    // 取消的任务：已完成的文件保留其结果，其余输入记为已取消
+    pub fn cancelled(provider: String, options: JobOptions, started_at: i64, inputs: &[PathBuf], mut files: Vec<FileOutcome>) -> Self {
+        for input in inputs {
+            if !files.iter().any(|f| &f.input == input) {
+                files.push(FileOutcome { input: input.clone(), output_dir: None, error: Some("Cancelled".to_string()) });
+            }
+        }
+        Self { status: JobStatus::Cancelled, ..Self::new(provider, options, started_at, files) }
+    }
+
+    pub fn output_dirs(&self) -> Vec<PathBuf> {
+        self.files.iter().filter_map(|f| f.output_dir.clone()).collect()
+    }
+
+    pub fn failed_inputs(&self) -> Vec<PathBuf> {
+        self.files.iter().filter(|f| f.error.is_some()).map(|f| f.input.clone()).collect()
+    }
+
+    pub fn started_at_display(&self) -> String {
+        format_timestamp(self.started_at)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct JobHistory {
+    // 按时间顺序排列，最新的在最后
+    pub jobs: Vec<JobRecord>,
+}
+
+impl JobHistory {
+    pub fn push(&mut self, job: JobRecord) {
+        self.jobs.push(job);
+        if self.jobs.len() > MAX_JOBS {
+            let excess = self.jobs.len() - MAX_JOBS;
+            self.jobs.drain(..excess);
+        }
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        self.jobs.retain(|j| j.id != id);
+    }
+}
+
+pub fn load_history() -> JobHistory {
+    load_json("history")
+}
+
+pub fn save_history(history: &JobHistory) -> anyhow::Result<()> {
+    save_json("history", history)
+}
+
+// 以本地时间显示 Unix 时间戳 (秒)
+pub fn format_timestamp(timestamp: i64) -> String {
+    use chrono::TimeZone;
+    chrono::Local.timestamp_opt(timestamp, 0)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancelled_jobs_keep_finished_files_and_rerun_the_rest() {
+        let inputs: Vec<PathBuf> = vec!["a.pdf".into(), "b.pdf".into(), "c.pdf".into()];
+        let done = vec![FileOutcome { input: "a.pdf".into(), output_dir: Some("ocr_a".into()), error: None }];
+        let job = JobRecord::cancelled("mock".into(), JobOptions::default(), 0, &inputs, done);
+
+        assert_eq!(job.status, JobStatus::Cancelled);
+        assert_eq!(job.output_dirs(), vec![PathBuf::from("ocr_a")]);
+        assert_eq!(job.failed_inputs(), inputs[1..].to_vec());
+    }
+}
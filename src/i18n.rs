@@ -40,8 +40,108 @@ impl I18n {
         zh.insert("cancel".into(), "取消".into());
         zh.insert("show".into(), "显示".into());
         zh.insert("hide".into(), "隐藏".into());
-        zh.insert("ocr_result_dir".into(), "ocr_结果_".into());
         zh.insert("success_all_files_done".into(), "所有文件处理完成！".into());
+        zh.insert("export_formats".into(), "导出格式".into());
+        zh.insert("history".into(), "历史记录".into());
+        zh.insert("history_title".into(), "任务历史".into());
+        zh.insert("no_history".into(), "暂无历史任务".into());
+        zh.insert("job_completed".into(), "已完成".into());
+        zh.insert("job_partially_failed".into(), "部分失败".into());
+        zh.insert("job_failed".into(), "失败".into());
+        zh.insert("job_cancelled".into(), "已取消".into());
+        zh.insert("open_results".into(), "打开结果".into());
+        zh.insert("rerun_failed".into(), "重新处理失败文件".into());
+        zh.insert("re_export".into(), "重新导出".into());
+        zh.insert("re_export_format".into(), "重新导出格式".into());
+        zh.insert("remove_entry".into(), "删除记录".into());
+        zh.insert("export_done".into(), "导出完成".into());
+        zh.insert("files_failed".into(), "个文件处理失败，可在历史记录中重新处理".into());
+        zh.insert("view_results".into(), "查看结果".into());
+        zh.insert("viewer_title".into(), "识别结果".into());
+        zh.insert("page".into(), "页码".into());
+        zh.insert("open_folder".into(), "打开文件夹".into());
+        zh.insert("edit".into(), "编辑".into());
+        zh.insert("discard_changes".into(), "放弃修改".into());
+        zh.insert("unsaved_changes".into(), "有未保存的修改".into());
+        zh.insert("corrections_saved".into(), "修改已保存，输出文件已重新生成".into());
+        zh.insert("provider_settings".into(), "引擎设置".into());
+        zh.insert("field_api_key".into(), "API Key".into());
+        zh.insert("field_base_url".into(), "API 地址".into());
+        zh.insert("field_model".into(), "模型".into());
+        zh.insert("field_include_images".into(), "提取图片".into());
+        zh.insert("field_prompt".into(), "提示词".into());
+        zh.insert("field_tesseract_binary".into(), "tesseract 程序路径".into());
+        zh.insert("field_languages".into(), "识别语言 (如 eng+chi_sim)".into());
+        zh.insert("field_psm".into(), "页面分割模式 (PSM)".into());
+        zh.insert("field_chain".into(), "供应商顺序 (ID，逗号分隔)".into());
+        zh.insert("cap_images_in".into(), "图片输入".into());
+        zh.insert("cap_images_out".into(), "返回图片".into());
+        zh.insert("cap_network".into(), "需要联网".into());
+        zh.insert("compare".into(), "对比模式".into());
+        zh.insert("compare_title".into(), "对比识别结果".into());
+        zh.insert("compare_hint".into(), "用两个引擎 (或同一引擎的两个模型) 处理同一文档并逐页对比".into());
+        zh.insert("compare_file".into(), "文档".into());
+        zh.insert("start_compare".into(), "开始对比".into());
+        zh.insert("compare_done".into(), "对比完成".into());
+        zh.insert("similarity".into(), "相似度".into());
+        zh.insert("open_report".into(), "打开 HTML 报告".into());
+        zh.insert("field_keep_uploaded_files".into(), "保留上传到服务器的文件".into());
+        zh.insert("cancel_process".into(), "取消处理".into());
+        zh.insert("cancelled".into(), "已取消".into());
+        zh.insert("list_uploads".into(), "列出已上传文件".into());
+        zh.insert("purge_uploads".into(), "删除所有已上传文件".into());
+        zh.insert("purge_done".into(), "已删除的文件数".into());
+        zh.insert("purge_failed".into(), "删除失败的文件数".into());
+        zh.insert("purge_skipped".into(), "未完成的批量任务仍在使用而保留的文件数".into());
+        zh.insert("no_uploads".into(), "服务器上没有已上传的文件".into());
+        zh.insert("maintenance_running".into(), "正在请求服务器...".into());
+        zh.insert("submit_batch".into(), "提交批量任务".into());
+        zh.insert("batch_jobs".into(), "批量任务".into());
+        zh.insert("batch_hint".into(), "批量任务费用更低，但可能需要数小时；关闭程序后重新打开会继续查询".into());
+        zh.insert("batch_submitted".into(), "批量任务已提交".into());
+        zh.insert("batch_completed".into(), "批量任务已完成".into());
+        zh.insert("batch_pending".into(), "等待中".into());
+        zh.insert("batch_poll_failed".into(), "查询失败，稍后重试".into());
+        zh.insert("batch_save_failed".into(), "部分结果保存失败，稍后重试".into());
+        zh.insert("refresh".into(), "刷新".into());
+        zh.insert("files".into(), "个文件".into());
+        zh.insert("annotation_schema".into(), "结构化提取 Schema".into());
+        zh.insert("annotation_schema_none".into(), "未设置".into());
+        zh.insert("image_format".into(), "图片格式".into());
+        zh.insert("jpeg_quality".into(), "质量".into());
+        zh.insert("path_template".into(), "输出路径".into());
+        zh.insert("image_layout".into(), "图片位置".into());
+        zh.insert("on_collision".into(), "目录已存在时".into());
+        zh.insert("collision_overwrite".into(), "覆盖".into());
+        zh.insert("collision_suffix".into(), "添加序号".into());
+        zh.insert("collision_skip".into(), "跳过".into());
+        zh.insert("keep_chunk_files".into(), "在 debug/ 中保留分块结果".into());
+        zh.insert("bundle".into(), "打包".into());
+        zh.insert("bundle_none".into(), "不打包".into());
+        zh.insert("bundle_per_document".into(), "每个文档一个 zip".into());
+        zh.insert("bundle_per_job".into(), "整批一个 zip".into());
+        zh.insert("front_matter".into(), "写入 YAML front matter".into());
+        zh.insert("add_field".into(), "添加字段".into());
+        zh.insert("field_key".into(), "字段名".into());
+        zh.insert("field_value".into(), "值".into());
+        zh.insert("page_marker".into(), "页面标记".into());
+        zh.insert("marker_none".into(), "无".into());
+        zh.insert("marker_heading".into(), "标题 (## Page N)".into());
+        zh.insert("marker_comment".into(), "HTML 注释".into());
+        zh.insert("marker_rule".into(), "水平线".into());
+        zh.insert("marker_custom".into(), "自定义".into());
+        zh.insert("strip_headers".into(), "删除页眉页脚".into());
+        zh.insert("stitch_pages".into(), "拼接跨页段落和表格".into());
+        zh.insert("toc".into(), "生成目录".into());
+        zh.insert("toc_from_outline".into(), "使用 PDF 书签".into());
+        zh.insert("chunk_size".into(), "分块大小".into());
+        zh.insert("chunk_overlap".into(), "重叠".into());
+        zh.insert("chunk_tokens".into(), "Token (估算)".into());
+        zh.insert("chunk_chars".into(), "字符".into());
+        zh.insert("headers_footers".into(), "页眉页脚".into());
+        zh.insert("no_headers_found".into(), "没有发现重复的页眉页脚".into());
+        zh.insert("remove_lines".into(), "删除这些行".into());
+        zh.insert("pages_count".into(), "页".into());
         
         translations.insert("zh_CN".into(), zh);
         
@@ -75,8 +175,108 @@ impl I18n {
         en.insert("cancel".into(), "Cancel".into());
         en.insert("show".into(), "Show".into());
         en.insert("hide".into(), "Hide".into());
-        en.insert("ocr_result_dir".into(), "ocr_results_".into());
         en.insert("success_all_files_done".into(), "All files processed successfully!".into());
+        en.insert("export_formats".into(), "Export Formats".into());
+        en.insert("history".into(), "History".into());
+        en.insert("history_title".into(), "Job History".into());
+        en.insert("no_history".into(), "No jobs yet".into());
+        en.insert("job_completed".into(), "Completed".into());
+        en.insert("job_partially_failed".into(), "Partially failed".into());
+        en.insert("job_failed".into(), "Failed".into());
+        en.insert("job_cancelled".into(), "Cancelled".into());
+        en.insert("open_results".into(), "Open Results".into());
+        en.insert("rerun_failed".into(), "Re-run Failed".into());
+        en.insert("re_export".into(), "Re-export".into());
+        en.insert("re_export_format".into(), "Re-export format".into());
+        en.insert("remove_entry".into(), "Remove".into());
+        en.insert("export_done".into(), "Export finished".into());
+        en.insert("files_failed".into(), "file(s) failed, re-run them from the history".into());
+        en.insert("view_results".into(), "View Results".into());
+        en.insert("viewer_title".into(), "OCR Results".into());
+        en.insert("page".into(), "Page".into());
+        en.insert("open_folder".into(), "Open Folder".into());
+        en.insert("edit".into(), "Edit".into());
+        en.insert("discard_changes".into(), "Discard".into());
+        en.insert("unsaved_changes".into(), "Unsaved changes".into());
+        en.insert("corrections_saved".into(), "Corrections saved, outputs regenerated".into());
+        en.insert("provider_settings".into(), "Engine Settings".into());
+        en.insert("field_api_key".into(), "API Key".into());
+        en.insert("field_base_url".into(), "Base URL".into());
+        en.insert("field_model".into(), "Model".into());
+        en.insert("field_include_images".into(), "Extract images".into());
+        en.insert("field_prompt".into(), "Prompt".into());
+        en.insert("field_tesseract_binary".into(), "tesseract executable".into());
+        en.insert("field_languages".into(), "Languages (e.g. eng+chi_sim)".into());
+        en.insert("field_psm".into(), "Page segmentation mode (PSM)".into());
+        en.insert("field_chain".into(), "Provider order (IDs, comma separated)".into());
+        en.insert("cap_images_in".into(), "Image input".into());
+        en.insert("cap_images_out".into(), "Returns images".into());
+        en.insert("cap_network".into(), "Needs network".into());
+        en.insert("compare".into(), "Compare".into());
+        en.insert("compare_title".into(), "Compare Results".into());
+        en.insert("compare_hint".into(), "Run one document through two engines (or two models of one engine) and diff them page by page".into());
+        en.insert("compare_file".into(), "Document".into());
+        en.insert("start_compare".into(), "Start Comparison".into());
+        en.insert("compare_done".into(), "Comparison finished".into());
+        en.insert("similarity".into(), "Similarity".into());
+        en.insert("open_report".into(), "Open HTML Report".into());
+        en.insert("field_keep_uploaded_files".into(), "Keep files uploaded to the server".into());
+        en.insert("cancel_process".into(), "Cancel".into());
+        en.insert("cancelled".into(), "Cancelled".into());
+        en.insert("list_uploads".into(), "List Uploaded Files".into());
+        en.insert("purge_uploads".into(), "Delete All Uploaded Files".into());
+        en.insert("purge_done".into(), "Files deleted".into());
+        en.insert("purge_failed".into(), "Files that could not be deleted".into());
+        en.insert("purge_skipped".into(), "Files kept for pending batch jobs".into());
+        en.insert("no_uploads".into(), "No uploaded files on the server".into());
+        en.insert("maintenance_running".into(), "Contacting server...".into());
+        en.insert("submit_batch".into(), "Submit as Batch".into());
+        en.insert("batch_jobs".into(), "Batch Jobs".into());
+        en.insert("batch_hint".into(), "Batch jobs are cheaper but may take hours; polling resumes when the app is reopened".into());
+        en.insert("batch_submitted".into(), "Batch job submitted".into());
+        en.insert("batch_completed".into(), "Batch job completed".into());
+        en.insert("batch_pending".into(), "Pending".into());
+        en.insert("batch_poll_failed".into(), "Poll failed, will retry".into());
+        en.insert("batch_save_failed".into(), "Some results could not be saved, will retry".into());
+        en.insert("refresh".into(), "Refresh".into());
+        en.insert("files".into(), "files".into());
+        en.insert("annotation_schema".into(), "Extraction Schema".into());
+        en.insert("annotation_schema_none".into(), "None".into());
+        en.insert("image_format".into(), "Image Format".into());
+        en.insert("jpeg_quality".into(), "Quality".into());
+        en.insert("path_template".into(), "Output Path".into());
+        en.insert("image_layout".into(), "Images".into());
+        en.insert("on_collision".into(), "If Exists".into());
+        en.insert("collision_overwrite".into(), "Overwrite".into());
+        en.insert("collision_suffix".into(), "Add Suffix".into());
+        en.insert("collision_skip".into(), "Skip".into());
+        en.insert("keep_chunk_files".into(), "Keep per-chunk results in debug/".into());
+        en.insert("bundle".into(), "Bundle".into());
+        en.insert("bundle_none".into(), "None".into());
+        en.insert("bundle_per_document".into(), "One zip per document".into());
+        en.insert("bundle_per_job".into(), "One zip per batch".into());
+        en.insert("front_matter".into(), "Write YAML front matter".into());
+        en.insert("add_field".into(), "Add Field".into());
+        en.insert("field_key".into(), "Key".into());
+        en.insert("field_value".into(), "Value".into());
+        en.insert("page_marker".into(), "Page Markers".into());
+        en.insert("marker_none".into(), "None".into());
+        en.insert("marker_heading".into(), "Heading (## Page N)".into());
+        en.insert("marker_comment".into(), "HTML Comment".into());
+        en.insert("marker_rule".into(), "Horizontal Rule".into());
+        en.insert("marker_custom".into(), "Custom".into());
+        en.insert("strip_headers".into(), "Remove headers/footers".into());
+        en.insert("stitch_pages".into(), "Join text and tables across pages".into());
+        en.insert("toc".into(), "Table of contents".into());
+        en.insert("toc_from_outline".into(), "Use PDF bookmarks".into());
+        en.insert("chunk_size".into(), "Chunk size".into());
+        en.insert("chunk_overlap".into(), "Overlap".into());
+        en.insert("chunk_tokens".into(), "Tokens (estimated)".into());
+        en.insert("chunk_chars".into(), "Characters".into());
+        en.insert("headers_footers".into(), "Headers/Footers".into());
+        en.insert("no_headers_found".into(), "No repeated headers or footers found".into());
+        en.insert("remove_lines".into(), "Remove These Lines".into());
+        en.insert("pages_count".into(), "pages".into());
         
         translations.insert("en_US".into(), en);
         
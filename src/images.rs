@@ -0,0 +1,142 @@
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::layout::ImageLayout;
+
+// 提取出的图片的保存格式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageExport {
+    // 保持供应商返回的原始数据
+    #[default]
+    Original,
+    Png,
+    Jpeg { quality: u8 },
+}
+
+impl ImageExport {
+    pub const DEFAULT_JPEG_QUALITY: u8 = 85;
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImageExport::Original => "Original",
+            ImageExport::Png => "PNG",
+            ImageExport::Jpeg { .. } => "JPEG",
+        }
+    }
+}
+
+// 解码 base64 或 data URL，返回图片数据及其实际格式对应的扩展名
+pub fn decode_image_data(data: &str) -> Result<(Vec<u8>, &'static str)> {
+    let (mime, payload) = match data.strip_prefix("data:").and_then(|rest| rest.split_once(',')) {
+        Some((header, payload)) => (header.split(';').next(), payload),
+        None => (None, data),
+    };
+    let bytes = general_purpose::STANDARD.decode(payload.trim())?;
+
+    // 优先相信文件头，其次是 data URL 中声明的类型
+    let extension = ::image::guess_format(&bytes)
+        .ok()
+        .and_then(|f| f.extensions_str().first().copied())
+        .or_else(|| mime.and_then(extension_for_mime))
+        .unwrap_or("bin");
+    Ok((bytes, extension))
+}
+
+fn extension_for_mime(mime: &str) -> Option<&'static str> {
+    match mime {
+        "image/jpeg" | "image/jpg" => Some("jpg"),
+        "image/png" => Some("png"),
+        "image/gif" => Some("gif"),
+        "image/webp" => Some("webp"),
+        "image/bmp" => Some("bmp"),
+        "image/tiff" => Some("tiff"),
+        _ => None,
+    }
+}
+
+fn mime_for_extension(extension: &str) -> &'static str {
+    match extension {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "tif" | "tiff" => "image/tiff",
+        _ => "application/octet-stream",
+    }
+}
+
+// 读取已保存的图片，编码为 data URI
+pub fn data_uri(path: &Path) -> Result<String> {
+    let bytes = std::fs::read(path)?;
+    let extension = path.extension().unwrap_or_default().to_string_lossy().to_lowercase();
+    Ok(format!("data:{};base64,{}", mime_for_extension(&extension), general_purpose::STANDARD.encode(bytes)))
+}
+
+// 按设置转换格式；无法解码的图片保持原样
+fn transcode(bytes: Vec<u8>, extension: &'static str, export: ImageExport) -> (Vec<u8>, &'static str) {
+    let target = match export {
+        ImageExport::Original => return (bytes, extension),
+        ImageExport::Png if extension == "png" => return (bytes, extension),
+        ImageExport::Png => ::image::ImageOutputFormat::Png,
+        ImageExport::Jpeg { quality } => ::image::ImageOutputFormat::Jpeg(quality.clamp(1, 100)),
+    };
+    let Ok(image) = ::image::load_from_memory(&bytes) else { return (bytes, extension) };
+    // JPEG 不支持透明通道
+    let image = match target {
+        ::image::ImageOutputFormat::Jpeg(_) => ::image::DynamicImage::ImageRgb8(image.to_rgb8()),
+        _ => image,
+    };
+    let mut encoded = Vec::new();
+    match image.write_to(&mut std::io::Cursor::new(&mut encoded), target) {
+        Ok(()) if export == ImageExport::Png => (encoded, "png"),
+        Ok(()) => (encoded, "jpg"),
+        Err(_) => (bytes, extension),
+    }
+}
+
+// 一个文档的图片目录：按设置转换格式，相同内容的图片只写入一次
+pub struct ImageStore {
+    images_dir: PathBuf,
+    // 相对于输出目录的路径前缀
+    prefix: &'static str,
+    export: ImageExport,
+    // 原始数据的 sha256 -> 相对于输出目录的路径
+    saved: HashMap<Vec<u8>, String>,
+}
+
+impl ImageStore {
+    pub fn new(out_dir: &Path, export: ImageExport, layout: ImageLayout) -> Self {
+        let (images_dir, prefix) = match layout {
+            ImageLayout::Nested => (out_dir.join("images"), "images/"),
+            ImageLayout::Flat => (out_dir.to_path_buf(), ""),
+        };
+        Self {
+            images_dir,
+            prefix,
+            export,
+            saved: HashMap::new(),
+        }
+    }
+
+    // 保存图片，返回相对于输出目录的路径；file_stem 不含扩展名
+    pub fn save(&mut self, data: &str, file_stem: &str) -> Result<String> {
+        let (bytes, extension) = decode_image_data(data)?;
+        let hash = Sha256::digest(&bytes).to_vec();
+        if let Some(existing) = self.saved.get(&hash) {
+            return Ok(existing.clone());
+        }
+
+        let (bytes, extension) = transcode(bytes, extension, self.export);
+        std::fs::create_dir_all(&self.images_dir)?;
+        let file_name = format!("{}.{}", file_stem, extension);
+        std::fs::write(self.images_dir.join(&file_name), bytes)?;
+
+        let relative = format!("{}{}", self.prefix, file_name);
+        self.saved.insert(hash, relative.clone());
+        Ok(relative)
+    }
+}
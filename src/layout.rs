@@ -0,0 +1,155 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+// 默认布局：<输出目录>/ocr_<文件名>/complete.md，与界面语言无关
+pub const DEFAULT_PATH_TEMPLATE: &str = "ocr_{stem}/complete.md";
+
+// 模板中可用的占位符
+pub const PLACEHOLDERS: &str = "{stem} {ext} {date} {time} {provider}";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageLayout {
+    // 图片放在文档目录下的 images/ 中
+    #[default]
+    Nested,
+    // 图片与 Markdown 放在同一目录
+    Flat,
+}
+
+impl ImageLayout {
+    pub const ALL: [ImageLayout; 2] = [ImageLayout::Nested, ImageLayout::Flat];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImageLayout::Nested => "images/",
+            ImageLayout::Flat => "flat",
+        }
+    }
+}
+
+// 文档目录已存在时的处理方式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CollisionPolicy {
+    #[default]
+    Overwrite,
+    // 在目录名后追加 _2、_3 ...
+    Suffix,
+    // 已有识别结果时跳过该文件
+    Skip,
+}
+
+impl CollisionPolicy {
+    pub const ALL: [CollisionPolicy; 3] = [CollisionPolicy::Overwrite, CollisionPolicy::Suffix, CollisionPolicy::Skip];
+
+    // i18n key
+    pub fn label(&self) -> &'static str {
+        match self {
+            CollisionPolicy::Overwrite => "collision_overwrite",
+            CollisionPolicy::Suffix => "collision_suffix",
+            CollisionPolicy::Skip => "collision_skip",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct OutputLayout {
+    // 相对于输出目录的 Markdown 路径模板，如 {date}/{stem}/{stem}.md；
+    // 所在目录即文档目录 (result.json、图片等)，文件名决定各导出格式的文件名
+    pub path_template: String,
+    #[serde(default)]
+    pub images: ImageLayout,
+    #[serde(default)]
+    pub collision: CollisionPolicy,
+}
+
+impl Default for OutputLayout {
+    fn default() -> Self {
+        Self {
+            path_template: DEFAULT_PATH_TEMPLATE.to_string(),
+            images: ImageLayout::default(),
+            collision: CollisionPolicy::default(),
+        }
+    }
+}
+
+// 某个输入文件的输出位置
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentTarget {
+    pub dir: PathBuf,
+    // 导出文件名 (不含扩展名)
+    pub output_name: String,
+    // 按 Skip 策略跳过，dir 为已有结果的目录
+    pub skip: bool,
+}
+
+impl OutputLayout {
+    pub fn resolve(&self, output_base: &Path, source: &Path, provider_id: &str) -> Result<DocumentTarget> {
+        let now = chrono::Local::now();
+        let rendered = self.path_template
+            .replace("{stem}", &source.file_stem().unwrap_or_default().to_string_lossy())
+            .replace("{ext}", &source.extension().unwrap_or_default().to_string_lossy())
+            .replace("{date}", &now.format("%Y-%m-%d").to_string())
+            .replace("{time}", &now.format("%H%M%S").to_string())
+            .replace("{provider}", provider_id);
+
+        // 模板只能指向输出目录内部
+        let relative = PathBuf::from(rendered.trim());
+        if relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+            return Err(anyhow::anyhow!("Invalid output path template: {}", self.path_template));
+        }
+        let output_name = relative.file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Invalid output path template: {}", self.path_template))?;
+        // 每个文档需要自己的目录，模板没有目录时以文件名作为目录
+        let dir = match relative.parent().filter(|p| !p.as_os_str().is_empty()) {
+            Some(parent) => output_base.join(parent),
+            None => output_base.join(&output_name),
+        };
+
+        let has_result = dir.join(crate::export::DOCUMENT_FILE).exists();
+        let target = |dir: PathBuf, skip: bool| DocumentTarget { dir, output_name: output_name.clone(), skip };
+        match self.collision {
+            CollisionPolicy::Overwrite => Ok(target(dir, false)),
+            CollisionPolicy::Skip => Ok(target(dir, has_result)),
+            CollisionPolicy::Suffix if !dir.exists() => Ok(target(dir, false)),
+            CollisionPolicy::Suffix => {
+                let name = dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+                let candidate = (2..)
+                    .map(|n| dir.with_file_name(format!("{}_{}", name, n)))
+                    .find(|d| !d.exists())
+                    .unwrap();
+                Ok(target(candidate, false))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_and_collision_policies() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = Path::new("/in/report.pdf");
+        let mut layout = OutputLayout {
+            path_template: "{provider}/{stem}/{stem}.md".into(),
+            ..Default::default()
+        };
+
+        let target = layout.resolve(dir.path(), source, "mistral").unwrap();
+        assert_eq!(target, DocumentTarget { dir: dir.path().join("mistral/report"), output_name: "report".into(), skip: false });
+
+        std::fs::create_dir_all(&target.dir).unwrap();
+        std::fs::write(target.dir.join(crate::export::DOCUMENT_FILE), "{}").unwrap();
+        layout.collision = CollisionPolicy::Suffix;
+        assert_eq!(layout.resolve(dir.path(), source, "mistral").unwrap().dir, dir.path().join("mistral/report_2"));
+        layout.collision = CollisionPolicy::Skip;
+        assert!(layout.resolve(dir.path(), source, "mistral").unwrap().skip);
+
+        layout.path_template = "../{stem}.md".into();
+        assert!(layout.resolve(dir.path(), source, "mistral").is_err());
+    }
+}
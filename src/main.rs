@@ -2,15 +2,53 @@ mod providers;
 mod pdf_utils;
 mod config;
 mod i18n;
+mod export;
+mod history;
+mod viewer;
+mod pipeline;
+mod compare;
+mod batch;
+mod images;
+mod layout;
+mod bundle;
+mod metadata;
+mod cleanup;
+mod stitch;
+mod tables;
+mod toc;
+mod chunks;
 
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use eframe::egui;
-use providers::{OcrProvider, OcrResult, mistral::MistralProvider};
-use config::{AppConfig, load_config, save_config};
+use providers::{AnnotationSchema, OcrOptions, OcrProvider};
+use providers::registry::{self, Capabilities, FieldKind, SettingsMap};
+use providers::replay::ReplayProvider;
+use config::{AppConfig, OutputOptions, load_config, save_config};
+use export::{ExportFormat, PageMarker};
+use images::ImageExport;
+use layout::{CollisionPolicy, ImageLayout};
+use bundle::BundleMode;
+use chunks::ChunkUnit;
+use history::{FileOutcome, JobHistory, JobOptions, JobRecord, JobStatus, load_history, save_history};
 use i18n::I18n;
-use tokio::sync::mpsc;
-use base64::{engine::general_purpose, Engine as _};
+use pipeline::{ProgressUpdate, process_single_file, re_export};
+use viewer::ResultViewer;
+use compare::{CompareSide, CompareViewer};
+use batch::{BatchJobs, BatchState, BatchUpdate, load_batch_jobs, save_batch_jobs};
+use tokio::sync::{mpsc, oneshot};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// The job currently running, recorded to the history when it finishes or is cancelled
+struct RunningJob {
+    provider: String,
+    options: JobOptions,
+    started_at: i64,
+    inputs: Vec<PathBuf>,
+    // 已处理完的文件
+    outcomes: Vec<FileOutcome>,
+}
 
 struct AppState {
     config: AppConfig,
@@ -21,14 +59,43 @@ struct AppState {
     current_file_progress: f32,
     status_message: String,
     is_processing: bool,
-    show_api_modal: bool,
-    temp_api_key: String,
+    show_settings_modal: bool,
+    // 设置窗口中正在编辑的字段值
+    temp_settings: HashMap<String, String>,
     show_key: bool,
     last_output_dirs: Vec<PathBuf>,
     receiver: Option<mpsc::Receiver<ProgressUpdate>>,
-    
-    // Available providers
-    available_providers: Vec<Box<dyn OcrProvider>>,
+    // 正在运行的任务，用于取消
+    task: Option<tokio::task::JoinHandle<()>>,
+    // 上传文件维护 (列出/清理) 的结果
+    maintenance_rx: Option<oneshot::Receiver<String>>,
+    maintenance_message: String,
+    // 当前供应商按已保存设置构建后的能力：(供应商 ID, 设置, 能力)
+    capabilities_cache: Option<(String, SettingsMap, Capabilities)>,
+
+    // Job history
+    history: JobHistory,
+    show_history: bool,
+    history_export_format: ExportFormat,
+    running_job: Option<RunningJob>,
+
+    viewer: Option<ResultViewer>,
+
+    // Compare mode: (provider id, model override) for side A and B
+    show_compare_setup: bool,
+    compare_sides: [(String, String); 2],
+    compare_file: usize,
+    compare_viewer: Option<CompareViewer>,
+
+    // Batch jobs, polled in the background until their results are saved
+    batch_jobs: BatchJobs,
+    show_batch: bool,
+    batch_tx: mpsc::Sender<BatchUpdate>,
+    batch_rx: mpsc::Receiver<BatchUpdate>,
+    batch_polling: bool,
+    last_batch_poll: Option<Instant>,
+    // 最近一次查询失败的原因，按任务 ID
+    batch_errors: HashMap<String, String>,
 }
 
 impl AppState {
@@ -38,11 +105,10 @@ impl AppState {
         let i18n = I18n::new(&config.language);
         let output_path = std::env::current_dir().unwrap_or_default();
         let status_message = i18n.t("ready").to_string();
-        
-        // Register providers here. Currently only Mistral.
-        // We use a dummy key for registration, the actual key is injected during processing.
-        let mistral = Box::new(MistralProvider::new("".to_string()));
-        let available_providers: Vec<Box<dyn OcrProvider>> = vec![mistral];
+
+        let history = load_history();
+        let last_output_dirs = history.jobs.last().map(|j| j.output_dirs()).unwrap_or_default();
+        let (batch_tx, batch_rx) = mpsc::channel(100);
         
         Self {
             config,
@@ -53,36 +119,162 @@ impl AppState {
             current_file_progress: 0.0,
             status_message,
             is_processing: false,
-            show_api_modal: false,
-            temp_api_key: String::new(),
+            show_settings_modal: false,
+            temp_settings: HashMap::new(),
             show_key: false,
-            last_output_dirs: Vec::new(),
+            last_output_dirs,
             receiver: None,
-            available_providers,
+            task: None,
+            maintenance_rx: None,
+            maintenance_message: String::new(),
+            capabilities_cache: None,
+            history,
+            show_history: false,
+            history_export_format: ExportFormat::Html,
+            running_job: None,
+            viewer: None,
+            show_compare_setup: false,
+            compare_sides: Default::default(),
+            compare_file: 0,
+            compare_viewer: None,
+            batch_jobs: load_batch_jobs(),
+            show_batch: false,
+            batch_tx,
+            batch_rx,
+            batch_polling: false,
+            last_batch_poll: None,
+            batch_errors: HashMap::new(),
         }
     }
 
     fn get_active_provider_name(&self) -> String {
-        for p in &self.available_providers {
-            if p.id() == self.config.active_provider {
-                return p.name().to_string();
+        registry::find(&self.config.active_provider)
+            .map(|d| d.name.to_string())
+            .unwrap_or_else(|| "Unknown".to_string())
+    }
+
+    // 组合供应商的能力取决于其成员，因此按已保存的设置构建后查询；
+    // 构建失败 (如缺少 API Key) 时使用注册信息中的值
+    fn active_capabilities(&mut self) -> Capabilities {
+        let id = &self.config.active_provider;
+        let settings = &self.config.provider_settings;
+        match &self.capabilities_cache {
+            Some((cached_id, cached_settings, caps)) if cached_id == id && cached_settings == settings => *caps,
+            _ => {
+                let caps = match registry::build(id, settings) {
+                    Ok(provider) => provider.capabilities(),
+                    Err(_) => registry::find(id).map(|d| d.capabilities).unwrap_or_default(),
+                };
+                self.capabilities_cache = Some((id.clone(), settings.clone(), caps));
+                caps
             }
         }
-        "Unknown".to_string()
     }
-}
 
-enum ProgressUpdate {
-    Total(f32),
-    Current(f32),
-    Message(String),
-    Finished(Vec<PathBuf>),
-    Error(String),
+    fn open_settings_modal(&mut self) {
+        self.temp_settings = self.config.provider_settings
+            .get(&self.config.active_provider)
+            .cloned()
+            .unwrap_or_default();
+        self.maintenance_message.clear();
+        self.show_settings_modal = true;
+    }
+
+    // 按当前设置生成任务选项
+    fn job_options(&self) -> JobOptions {
+        JobOptions {
+            output_base: self.output_path.clone(),
+            output: self.config.output.clone(),
+        }
+    }
+
+    // 本次任务传给供应商的选项
+    fn ocr_options(output: &OutputOptions) -> anyhow::Result<OcrOptions> {
+        let annotation_schema = match &output.annotation_schema {
+            Some(path) => Some(AnnotationSchema::load(path)
+                .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?),
+            None => None,
+        };
+        Ok(OcrOptions { annotation_schema })
+    }
+
+    // 取消正在运行的任务；被中断的上传会在后台清理
+    fn cancel_processing(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+        self.is_processing = false;
+        // 收取取消前已完成的文件，连同未处理的输入一起记入历史
+        if let (Some(mut rx), Some(mut running)) = (self.receiver.take(), self.running_job.take()) {
+            while let Ok(update) = rx.try_recv() {
+                if let ProgressUpdate::FileDone(outcome) = update {
+                    running.outcomes.push(outcome);
+                }
+            }
+            let job = JobRecord::cancelled(running.provider, running.options, running.started_at, &running.inputs, running.outcomes);
+            self.last_output_dirs = job.output_dirs();
+            self.history.push(job);
+            let _ = save_history(&self.history);
+        }
+        self.running_job = None;
+        self.status_message = self.i18n.t("cancelled").to_string();
+    }
+
+    // 在后台对当前供应商执行上传文件的列出或清理
+    fn run_upload_maintenance(&mut self, ctx: egui::Context, purge: bool) {
+        let provider = match registry::build(&self.config.active_provider, &self.config.provider_settings) {
+            Ok(p) => p,
+            Err(e) => {
+                self.maintenance_message = format!("Error: {}", e);
+                return;
+            }
+        };
+        let purge_done = self.i18n.t("purge_done").to_string();
+        let purge_failed = self.i18n.t("purge_failed").to_string();
+        let purge_skipped = self.i18n.t("purge_skipped").to_string();
+        let pending_batches: Vec<(String, String)> = self.batch_jobs.jobs.iter()
+            .filter(|j| j.is_pending())
+            .map(|j| (j.provider.clone(), j.id.clone()))
+            .collect();
+        let no_uploads = self.i18n.t("no_uploads").to_string();
+        let (tx, rx) = oneshot::channel();
+        self.maintenance_rx = Some(rx);
+        self.maintenance_message = self.i18n.t("maintenance_running").to_string();
+
+        tokio::spawn(async move {
+            let message = if purge {
+                match provider.purge_uploads(&pending_batches).await {
+                    Ok(report) => {
+                        let mut message = format!("{}: {}", purge_done, report.deleted);
+                        if report.skipped > 0 {
+                            message.push_str(&format!("\n{}: {}", purge_skipped, report.skipped));
+                        }
+                        if !report.failed.is_empty() {
+                            message.push_str(&format!("\n{}: {}\n{}", purge_failed, report.failed.len(), report.failed.join("\n")));
+                        }
+                        message
+                    }
+                    Err(e) => format!("Error: {}", e),
+                }
+            } else {
+                match provider.list_uploads().await {
+                    Ok(files) if files.is_empty() => no_uploads,
+                    Ok(files) => files.join("\n"),
+                    Err(e) => format!("Error: {}", e),
+                }
+            };
+            let _ = tx.send(message);
+            ctx.request_repaint();
+        });
+    }
 }
 
 impl eframe::App for AppState {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        let mut finished_dirs = None;
+        let mut finished_files = None;
+        let mut compared_dir = None;
+        let mut submitted_batch = None;
+        let mut exported = false;
         let mut error_msg = None;
 
         if let Some(ref mut rx) = self.receiver {
@@ -91,9 +283,21 @@ impl eframe::App for AppState {
                     ProgressUpdate::Total(p) => self.total_progress = p,
                     ProgressUpdate::Current(p) => self.current_file_progress = p,
                     ProgressUpdate::Message(m) => self.status_message = m,
-                    ProgressUpdate::Finished(dirs) => {
-                        finished_dirs = Some(dirs);
+                    ProgressUpdate::FileDone(outcome) => {
+                        if let Some(running) = &mut self.running_job {
+                            running.outcomes.push(outcome);
+                        }
+                    }
+                    ProgressUpdate::Finished(files) => {
+                        finished_files = Some(files);
                     }
+                    ProgressUpdate::Compared(dir) => {
+                        compared_dir = Some(dir);
+                    }
+                    ProgressUpdate::BatchSubmitted(job) => {
+                        submitted_batch = Some(*job);
+                    }
+                    ProgressUpdate::Exported => exported = true,
                     ProgressUpdate::Error(e) => {
                         error_msg = Some(e);
                     }
@@ -101,17 +305,61 @@ impl eframe::App for AppState {
             }
         }
 
-        if let Some(dirs) = finished_dirs {
-            self.last_output_dirs = dirs;
+        if let Some(rx) = &mut self.maintenance_rx {
+            if let Ok(message) = rx.try_recv() {
+                self.maintenance_message = message;
+                self.maintenance_rx = None;
+            }
+        }
+
+        if let Some(files) = finished_files {
             self.is_processing = false;
-            self.status_message = self.i18n.t("success_all_files_done").to_string();
             self.receiver = None;
+            self.task = None;
+            if let Some(running) = self.running_job.take() {
+                let job = JobRecord::new(running.provider, running.options, running.started_at, files);
+                self.last_output_dirs = job.output_dirs();
+                self.status_message = match job.status {
+                    JobStatus::Completed => self.i18n.t("success_all_files_done").to_string(),
+                    _ => format!("{} {}", job.failed_inputs().len(), self.i18n.t("files_failed")),
+                };
+                self.history.push(job);
+                let _ = save_history(&self.history);
+            }
         }
 
+        if exported {
+            self.is_processing = false;
+            self.receiver = None;
+            self.task = None;
+            self.status_message = self.i18n.t("export_done").to_string();
+        }
+
+        if let Some(dir) = compared_dir {
+            self.is_processing = false;
+            self.receiver = None;
+            self.task = None;
+            self.status_message = self.i18n.t("compare_done").to_string();
+            self.compare_viewer = Some(CompareViewer::new(dir));
+        }
+
+        if let Some(job) = submitted_batch {
+            self.is_processing = false;
+            self.receiver = None;
+            self.task = None;
+            self.status_message = format!("{}: {}", self.i18n.t("batch_submitted"), job.id);
+            self.batch_jobs.jobs.push(job);
+            let _ = save_batch_jobs(&self.batch_jobs);
+            self.show_batch = true;
+        }
+
+        self.handle_batch_updates(ctx);
+
         if let Some(e) = error_msg {
             self.status_message = e;
             self.is_processing = false;
             self.receiver = None;
+            self.task = None;
         }
 
         egui::CentralPanel::default().show(ctx, |ui| {
@@ -133,8 +381,32 @@ impl eframe::App for AppState {
             self.render_buttons(ui);
         });
 
-        if self.show_api_modal {
-            self.render_api_modal(ctx);
+        if self.show_settings_modal {
+            self.render_settings_modal(ctx);
+        }
+
+        if self.show_history {
+            self.render_history(ctx);
+        }
+
+        if self.show_compare_setup {
+            self.render_compare_setup(ctx);
+        }
+
+        if self.show_batch {
+            self.render_batch_jobs(ctx);
+        }
+
+        if let Some(viewer) = &mut self.compare_viewer {
+            if !viewer.show(ctx, &self.i18n) {
+                self.compare_viewer = None;
+            }
+        }
+
+        if let Some(viewer) = &mut self.viewer {
+            if !viewer.show(ctx, &self.i18n, &self.config.output.export_formats) {
+                self.viewer = None;
+            }
         }
         
         if self.is_processing {
@@ -172,8 +444,8 @@ impl AppState {
                 egui::ComboBox::from_id_source("provider_combo")
                     .selected_text(self.get_active_provider_name())
                     .show_ui(ui, |ui| {
-                        for p in &self.available_providers {
-                            if ui.selectable_value(&mut provider_id, p.id().to_string(), p.name()).clicked() {
+                        for p in registry::all() {
+                            if ui.selectable_value(&mut provider_id, p.id.to_string(), p.name).clicked() {
                                 provider_changed = true;
                             }
                         }
@@ -275,6 +547,215 @@ impl AppState {
                     }
                 }
             });
+            ui.horizontal(|ui| {
+                ui.label(self.i18n.t("export_formats"));
+                let mut changed = false;
+                for format in ExportFormat::ALL {
+                    let mut enabled = self.config.output.export_formats.contains(&format);
+                    // Markdown is always produced
+                    if format == ExportFormat::Markdown {
+                        ui.add_enabled(false, egui::Checkbox::new(&mut true, format.label()));
+                        continue;
+                    }
+                    if ui.checkbox(&mut enabled, format.label()).changed() {
+                        if enabled {
+                            self.config.output.export_formats.push(format);
+                        } else {
+                            self.config.output.export_formats.retain(|f| *f != format);
+                        }
+                        changed = true;
+                    }
+                }
+                if changed {
+                    let _ = save_config(&self.config);
+                }
+            });
+
+            if self.config.output.export_formats.contains(&ExportFormat::Chunks) {
+                ui.horizontal(|ui| {
+                    ui.label(self.i18n.t("chunk_size"));
+                    let before = self.config.output.chunking;
+                    let chunking = &mut self.config.output.chunking;
+                    ui.add(egui::DragValue::new(&mut chunking.size).range(1..=100_000));
+                    egui::ComboBox::from_id_source("chunk_unit_combo")
+                        .selected_text(self.i18n.t(chunking.unit.label()))
+                        .show_ui(ui, |ui| {
+                            for unit in [ChunkUnit::Tokens, ChunkUnit::Chars] {
+                                ui.selectable_value(&mut chunking.unit, unit, self.i18n.t(unit.label()));
+                            }
+                        });
+                    ui.label(self.i18n.t("chunk_overlap"));
+                    let max_overlap = chunking.size / 2;
+                    ui.add(egui::DragValue::new(&mut chunking.overlap).range(0..=max_overlap));
+                    if self.config.output.chunking != before {
+                        let _ = save_config(&self.config);
+                    }
+                });
+            }
+
+            ui.horizontal(|ui| {
+                ui.label(self.i18n.t("image_format"));
+                let before = self.config.output.image_export;
+                let jpeg = ImageExport::Jpeg { quality: ImageExport::DEFAULT_JPEG_QUALITY };
+                egui::ComboBox::from_id_source("image_export_combo")
+                    .selected_text(self.config.output.image_export.label())
+                    .show_ui(ui, |ui| {
+                        for option in [ImageExport::Original, ImageExport::Png, jpeg] {
+                            let selected = std::mem::discriminant(&self.config.output.image_export) == std::mem::discriminant(&option);
+                            if ui.selectable_label(selected, option.label()).clicked() && !selected {
+                                self.config.output.image_export = option;
+                            }
+                        }
+                    });
+                if let ImageExport::Jpeg { quality } = &mut self.config.output.image_export {
+                    ui.label(self.i18n.t("jpeg_quality"));
+                    ui.add(egui::DragValue::new(quality).range(1..=100));
+                }
+                if self.config.output.image_export != before {
+                    let _ = save_config(&self.config);
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(self.i18n.t("path_template"));
+                let before = self.config.output.layout.clone();
+                let layout = &mut self.config.output.layout;
+                ui.add(egui::TextEdit::singleline(&mut layout.path_template).desired_width(220.0))
+                    .on_hover_text(layout::PLACEHOLDERS);
+                ui.label(self.i18n.t("image_layout"));
+                egui::ComboBox::from_id_source("image_layout_combo")
+                    .selected_text(layout.images.label())
+                    .show_ui(ui, |ui| {
+                        for option in ImageLayout::ALL {
+                            ui.selectable_value(&mut layout.images, option, option.label());
+                        }
+                    });
+                ui.label(self.i18n.t("on_collision"));
+                egui::ComboBox::from_id_source("collision_combo")
+                    .selected_text(self.i18n.t(layout.collision.label()))
+                    .show_ui(ui, |ui| {
+                        for option in CollisionPolicy::ALL {
+                            ui.selectable_value(&mut layout.collision, option, self.i18n.t(option.label()));
+                        }
+                    });
+                if self.config.output.layout != before {
+                    let _ = save_config(&self.config);
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.checkbox(&mut self.config.output.keep_chunk_files, self.i18n.t("keep_chunk_files")).changed() {
+                    let _ = save_config(&self.config);
+                }
+                ui.label(self.i18n.t("bundle"));
+                let before = self.config.output.bundle;
+                egui::ComboBox::from_id_source("bundle_combo")
+                    .selected_text(self.i18n.t(self.config.output.bundle.label()))
+                    .show_ui(ui, |ui| {
+                        for option in BundleMode::ALL {
+                            ui.selectable_value(&mut self.config.output.bundle, option, self.i18n.t(option.label()));
+                        }
+                    });
+                if self.config.output.bundle != before {
+                    let _ = save_config(&self.config);
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(self.i18n.t("page_marker"));
+                let before = self.config.output.page_marker.clone();
+                let custom = match &self.config.output.page_marker {
+                    PageMarker::Custom(template) => template.clone(),
+                    _ => PageMarker::DEFAULT_CUSTOM_TEMPLATE.to_string(),
+                };
+                egui::ComboBox::from_id_source("page_marker_combo")
+                    .selected_text(self.i18n.t(self.config.output.page_marker.label()))
+                    .show_ui(ui, |ui| {
+                        for option in [PageMarker::None, PageMarker::Heading, PageMarker::Comment, PageMarker::Rule, PageMarker::Custom(custom)] {
+                            let selected = std::mem::discriminant(&self.config.output.page_marker) == std::mem::discriminant(&option);
+                            if ui.selectable_label(selected, self.i18n.t(option.label())).clicked() && !selected {
+                                self.config.output.page_marker = option;
+                            }
+                        }
+                    });
+                if let PageMarker::Custom(template) = &mut self.config.output.page_marker {
+                    ui.add(egui::TextEdit::singleline(template).desired_width(160.0))
+                        .on_hover_text("{page}");
+                }
+                if self.config.output.page_marker != before {
+                    let _ = save_config(&self.config);
+                }
+                ui.separator();
+                if ui.checkbox(&mut self.config.output.strip_headers, self.i18n.t("strip_headers")).changed() {
+                    let _ = save_config(&self.config);
+                }
+                if ui.checkbox(&mut self.config.output.stitch_pages, self.i18n.t("stitch_pages")).changed() {
+                    let _ = save_config(&self.config);
+                }
+                ui.separator();
+                if ui.checkbox(&mut self.config.output.toc, self.i18n.t("toc")).changed() {
+                    let _ = save_config(&self.config);
+                }
+                let toc = self.config.output.toc;
+                if ui.add_enabled(toc, egui::Checkbox::new(&mut self.config.output.toc_from_outline, self.i18n.t("toc_from_outline"))).changed() {
+                    let _ = save_config(&self.config);
+                }
+            });
+
+            let before = self.config.output.front_matter.clone();
+            let front_matter = &mut self.config.output.front_matter;
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut front_matter.enabled, self.i18n.t("front_matter"));
+                if front_matter.enabled && ui.button(self.i18n.t("add_field")).clicked() {
+                    front_matter.extra.push((String::new(), String::new()));
+                }
+            });
+            if front_matter.enabled {
+                let mut remove = None;
+                for (i, (key, value)) in front_matter.extra.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(key).hint_text(self.i18n.t("field_key")).desired_width(120.0));
+                        ui.add(egui::TextEdit::singleline(value).hint_text(self.i18n.t("field_value")).desired_width(220.0));
+                        if ui.button("❌").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = remove {
+                    front_matter.extra.remove(i);
+                }
+            }
+            if self.config.output.front_matter != before {
+                let _ = save_config(&self.config);
+            }
+
+            let supports_annotations = self.active_capabilities().supports_annotations;
+            ui.add_enabled_ui(supports_annotations, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(self.i18n.t("annotation_schema"));
+                    let mut changed = false;
+                    match &self.config.output.annotation_schema {
+                        Some(path) => {
+                            ui.label(path.file_name().unwrap_or_default().to_string_lossy());
+                            if ui.button("❌").clicked() {
+                                self.config.output.annotation_schema = None;
+                                changed = true;
+                            }
+                        }
+                        None => {
+                            ui.label(self.i18n.t("annotation_schema_none"));
+                        }
+                    }
+                    if ui.button(self.i18n.t("browse_button")).clicked() {
+                        if let Some(path) = rfd::FileDialog::new().add_filter("JSON Schema", &["json"]).pick_file() {
+                            self.config.output.annotation_schema = Some(path);
+                            changed = true;
+                        }
+                    }
+                    if changed {
+                        let _ = save_config(&self.config);
+                    }
+                });
+            });
         });
     }
 
@@ -293,18 +774,15 @@ impl AppState {
         ui.horizontal(|ui| {
             let start_btn = ui.add_enabled(!self.is_processing && !self.file_queue.is_empty(), egui::Button::new(self.i18n.t("start_process")));
             if start_btn.clicked() {
-                // Check if key exists for current provider
-                let current_key = self.config.api_keys.get(&self.config.active_provider).cloned().unwrap_or_default();
-                if current_key.is_empty() {
-                    self.show_api_modal = true;
-                } else {
-                    self.start_processing(ui.ctx().clone());
-                }
+                self.request_start(ui.ctx().clone());
             }
 
-            if ui.button(self.i18n.t("set_api_key")).clicked() {
-                self.temp_api_key = self.config.api_keys.get(&self.config.active_provider).cloned().unwrap_or_default();
-                self.show_api_modal = true;
+            if self.is_processing && ui.button(self.i18n.t("cancel_process")).clicked() {
+                self.cancel_processing();
+            }
+
+            if ui.button(self.i18n.t("provider_settings")).clicked() {
+                self.open_settings_modal();
             }
 
             let browse_btn = ui.add_enabled(!self.last_output_dirs.is_empty(), egui::Button::new(self.i18n.t("browse_results")));
@@ -313,216 +791,619 @@ impl AppState {
                     let _ = open::that(dir);
                 }
             }
+
+            let view_btn = ui.add_enabled(!self.last_output_dirs.is_empty(), egui::Button::new(self.i18n.t("view_results")));
+            if view_btn.clicked() {
+                self.viewer = Some(ResultViewer::new(self.last_output_dirs.clone()));
+            }
+
+            if ui.button(self.i18n.t("history")).clicked() {
+                self.show_history = !self.show_history;
+            }
+
+            if ui.button(self.i18n.t("compare")).clicked() {
+                self.show_compare_setup = !self.show_compare_setup;
+            }
+
+            let supports_batch = self.active_capabilities().supports_batch;
+            if supports_batch {
+                let submit_btn = ui.add_enabled(!self.is_processing && !self.file_queue.is_empty(), egui::Button::new(self.i18n.t("submit_batch")));
+                if submit_btn.clicked() {
+                    self.start_batch(ui.ctx().clone());
+                }
+            }
+
+            if !self.batch_jobs.jobs.is_empty() && ui.button(self.i18n.t("batch_jobs")).clicked() {
+                self.show_batch = !self.show_batch;
+            }
         });
         ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
             ui.label(self.i18n.t("copyright"));
         });
     }
 
-    fn render_api_modal(&mut self, ctx: &egui::Context) {
-        let provider_name = self.get_active_provider_name();
-        let title = format!("{} {} API Key", self.i18n.t("set_api_key"), provider_name);
+    // 设置表单根据供应商注册的字段自动生成
+    fn render_settings_modal(&mut self, ctx: &egui::Context) {
+        let Some(descriptor) = registry::find(&self.config.active_provider) else {
+            self.show_settings_modal = false;
+            return;
+        };
+        let title = format!("{} - {}", descriptor.name, self.i18n.t("provider_settings"));
         
         egui::Window::new(title)
             .collapsible(false)
             .resizable(false)
             .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
             .show(ctx, |ui| {
-                ui.label(self.i18n.t("api_key_prompt"));
-                ui.horizontal(|ui| {
-                    ui.add(egui::TextEdit::singleline(&mut self.temp_api_key).password(!self.show_key));
-                    if ui.button(if self.show_key { self.i18n.t("hide") } else { self.i18n.t("show") }).clicked() {
-                        self.show_key = !self.show_key;
+                egui::Grid::new("provider_settings_grid").num_columns(2).show(ui, |ui| {
+                    for field in descriptor.fields {
+                        let label = if field.required {
+                            format!("{} *", self.i18n.t(field.label))
+                        } else {
+                            self.i18n.t(field.label).to_string()
+                        };
+                        ui.label(label);
+
+                        let value = self.temp_settings.entry(field.key.to_string())
+                            .or_insert_with(|| field.default.to_string());
+                        match field.kind {
+                            FieldKind::Secret => {
+                                ui.horizontal(|ui| {
+                                    ui.add(egui::TextEdit::singleline(value).password(!self.show_key));
+                                    if ui.button(if self.show_key { self.i18n.t("hide") } else { self.i18n.t("show") }).clicked() {
+                                        self.show_key = !self.show_key;
+                                    }
+                                });
+                            }
+                            FieldKind::Bool => {
+                                let mut checked = value == "true";
+                                if ui.checkbox(&mut checked, "").changed() {
+                                    *value = checked.to_string();
+                                }
+                            }
+                            FieldKind::Multiline => {
+                                ui.add(egui::TextEdit::multiline(value).desired_rows(4));
+                            }
+                            FieldKind::Text | FieldKind::Url => {
+                                ui.add(egui::TextEdit::singleline(value).hint_text(field.default));
+                            }
+                        }
+                        ui.end_row();
                     }
                 });
-                
-                // Show link only for Mistral for now, or make it dynamic
-                if self.config.active_provider == "mistral" {
-                    ui.hyperlink_to(self.i18n.t("apply_here"), "https://console.mistral.ai/");
+
+                let caps = self.active_capabilities();
+                let yes_no = |b: bool| if b { "✅" } else { "❌" };
+                ui.label(format!(
+                    "PDF {}  {} {}  {} {}  {} {}",
+                    yes_no(caps.accepts_pdf),
+                    self.i18n.t("cap_images_in"), yes_no(caps.accepts_images),
+                    self.i18n.t("cap_images_out"), yes_no(caps.returns_images),
+                    self.i18n.t("cap_network"), yes_no(caps.requires_network),
+                ));
+
+                if let Some(url) = descriptor.help_url {
+                    ui.hyperlink_to(self.i18n.t("apply_here"), url);
                 }
                 
-                ui.label(self.i18n.t("api_activation_note"));
+                if caps.requires_network {
+                    ui.label(self.i18n.t("api_activation_note"));
+                }
+
+                // 供应商会保存上传的文档时，提供列出和清理的维护操作 (使用已保存的设置)
+                if caps.stores_uploads {
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        let idle = self.maintenance_rx.is_none();
+                        if ui.add_enabled(idle, egui::Button::new(self.i18n.t("list_uploads"))).clicked() {
+                            self.run_upload_maintenance(ui.ctx().clone(), false);
+                        }
+                        if ui.add_enabled(idle, egui::Button::new(self.i18n.t("purge_uploads"))).clicked() {
+                            self.run_upload_maintenance(ui.ctx().clone(), true);
+                        }
+                    });
+                    if !self.maintenance_message.is_empty() {
+                        egui::ScrollArea::vertical().max_height(120.0).show(ui, |ui| {
+                            ui.label(&self.maintenance_message);
+                        });
+                    }
+                }
+
+                let missing_required = descriptor.fields.iter()
+                    .filter(|f| f.required)
+                    .any(|f| self.temp_settings.get(f.key).map(|v| v.trim().is_empty()).unwrap_or(true));
                 
                 ui.horizontal(|ui| {
-                    if ui.button(self.i18n.t("save")).clicked() {
-                        if !self.temp_api_key.trim().is_empty() {
-                            self.config.api_keys.insert(self.config.active_provider.clone(), self.temp_api_key.trim().to_string());
-                            let _ = save_config(&self.config);
-                            self.show_api_modal = false;
-                        }
+                    if ui.add_enabled(!missing_required, egui::Button::new(self.i18n.t("save"))).clicked() {
+                        let values = self.temp_settings.iter()
+                            .map(|(k, v)| (k.clone(), v.trim().to_string()))
+                            .collect();
+                        self.config.provider_settings.insert(descriptor.id.to_string(), values);
+                        let _ = save_config(&self.config);
+                        self.show_settings_modal = false;
                     }
                     if ui.button(self.i18n.t("cancel")).clicked() {
-                        self.show_api_modal = false;
+                        self.show_settings_modal = false;
+                    }
+                });
+            });
+    }
+
+    fn render_history(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_history;
+        let mut to_remove = None;
+        let mut to_rerun = None;
+        let mut to_export = None;
+        let mut to_view = None;
+
+        egui::Window::new(self.i18n.t("history_title"))
+            .open(&mut open)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(self.i18n.t("re_export_format"));
+                    egui::ComboBox::from_id_source("history_export_combo")
+                        .selected_text(self.history_export_format.label())
+                        .show_ui(ui, |ui| {
+                            for format in ExportFormat::ALL {
+                                ui.selectable_value(&mut self.history_export_format, format, format.label());
+                            }
+                        });
+                });
+                ui.separator();
+
+                if self.history.jobs.is_empty() {
+                    ui.label(self.i18n.t("no_history"));
+                    return;
+                }
+
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    for job in self.history.jobs.iter().rev() {
+                        let status = match job.status {
+                            JobStatus::Completed => self.i18n.t("job_completed"),
+                            JobStatus::PartiallyFailed => self.i18n.t("job_partially_failed"),
+                            JobStatus::Failed => self.i18n.t("job_failed"),
+                            JobStatus::Cancelled => self.i18n.t("job_cancelled"),
+                        };
+                        let header = format!("{}  {}  {} ({})", job.started_at_display(), job.provider, status, job.files.len());
+
+                        egui::CollapsingHeader::new(header).id_source(&job.id).show(ui, |ui| {
+                            ui.label(format!("{} {}", self.i18n.t("save_location"), job.options.output_base.display()));
+                            for file in &job.files {
+                                let name = file.input.file_name().unwrap_or_default().to_string_lossy().to_string();
+                                match &file.error {
+                                    Some(e) => { ui.colored_label(ui.visuals().error_fg_color, format!("❌ {}: {}", name, e)); }
+                                    None => { ui.label(format!("✅ {}", name)); }
+                                }
+                            }
+
+                            ui.horizontal(|ui| {
+                                let dirs = job.output_dirs();
+                                if ui.add_enabled(!dirs.is_empty(), egui::Button::new(self.i18n.t("open_results"))).clicked() {
+                                    for dir in &dirs {
+                                        let _ = open::that(dir);
+                                    }
+                                }
+                                if ui.add_enabled(!dirs.is_empty(), egui::Button::new(self.i18n.t("view_results"))).clicked() {
+                                    to_view = Some(dirs.clone());
+                                }
+                                let can_rerun = !self.is_processing && !job.failed_inputs().is_empty();
+                                if ui.add_enabled(can_rerun, egui::Button::new(self.i18n.t("rerun_failed"))).clicked() {
+                                    to_rerun = Some(job.clone());
+                                }
+                                if ui.add_enabled(!self.is_processing && !dirs.is_empty(), egui::Button::new(self.i18n.t("re_export"))).clicked() {
+                                    to_export = Some(dirs);
+                                }
+                                if ui.button(self.i18n.t("remove_entry")).clicked() {
+                                    to_remove = Some(job.id.clone());
+                                }
+                            });
+                        });
                     }
                 });
             });
+
+        self.show_history = open;
+
+        if let Some(id) = to_remove {
+            self.history.remove(&id);
+            let _ = save_history(&self.history);
+        }
+
+        // 按任务记录中的供应商和选项重新运行，不改动当前的设置
+        if let Some(job) = to_rerun {
+            self.start_processing(ctx.clone(), job.provider.clone(), job.failed_inputs(), job.options.clone());
+        }
+
+        if let Some(dirs) = to_view {
+            self.viewer = Some(ResultViewer::new(dirs));
+        }
+
+        if let Some(dirs) = to_export {
+            self.start_re_export(ctx.clone(), dirs);
+        }
     }
 
-    fn start_processing(&mut self, ctx: egui::Context) {
+    // 重新导出要读写每个结果目录，在后台线程中进行
+    fn start_re_export(&mut self, ctx: egui::Context, dirs: Vec<PathBuf>) {
         self.is_processing = true;
-        self.last_output_dirs.clear();
-        
-        let provider_id = self.config.active_provider.clone();
-        let api_key = self.config.api_keys.get(&provider_id).cloned().unwrap_or_default();
-        
-        let files = self.file_queue.clone();
+        let format = self.history_export_format;
+        let (tx, rx) = mpsc::channel(100);
+        self.receiver = Some(rx);
+
+        self.task = Some(tokio::spawn(async move {
+            let update = match tokio::task::spawn_blocking(move || re_export(&dirs, format)).await {
+                Ok(Ok(())) => ProgressUpdate::Exported,
+                Ok(Err(e)) => ProgressUpdate::Error(format!("Error: {}", e)),
+                Err(e) => ProgressUpdate::Error(format!("Error: {}", e)),
+            };
+            let _ = tx.send(update).await;
+            ctx.request_repaint();
+        }));
+    }
+
+    fn render_compare_setup(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_compare_setup;
+        let mut run = false;
+
+        if self.compare_sides[0].0.is_empty() {
+            self.compare_sides[0].0 = self.config.active_provider.clone();
+            self.compare_sides[1].0 = self.config.active_provider.clone();
+        }
+        self.compare_file = self.compare_file.min(self.file_queue.len().saturating_sub(1));
+
+        egui::Window::new(self.i18n.t("compare_title"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(self.i18n.t("compare_hint"));
+                egui::Grid::new("compare_grid").num_columns(2).show(ui, |ui| {
+                    ui.label(self.i18n.t("compare_file"));
+                    let selected_name = self.file_queue.get(self.compare_file)
+                        .map(|f| f.file_name().unwrap_or_default().to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    egui::ComboBox::from_id_source("compare_file_combo")
+                        .selected_text(selected_name)
+                        .show_ui(ui, |ui| {
+                            for (i, file) in self.file_queue.iter().enumerate() {
+                                ui.selectable_value(&mut self.compare_file, i, file.file_name().unwrap_or_default().to_string_lossy());
+                            }
+                        });
+                    ui.end_row();
+
+                    for (i, side) in ["A", "B"].iter().enumerate() {
+                        let (provider_id, model) = &mut self.compare_sides[i];
+                        ui.label(*side);
+                        ui.horizontal(|ui| {
+                            let descriptor = registry::find(provider_id);
+                            egui::ComboBox::from_id_source(format!("compare_provider_{}", i))
+                                .selected_text(descriptor.map(|d| d.name).unwrap_or("Unknown"))
+                                .show_ui(ui, |ui| {
+                                    for p in registry::all() {
+                                        ui.selectable_value(provider_id, p.id.to_string(), p.name);
+                                    }
+                                });
+                            // Only providers with a model setting can be compared against another model
+                            if let Some(field) = descriptor.and_then(|d| d.fields.iter().find(|f| f.key == "model")) {
+                                ui.label(self.i18n.t("field_model"));
+                                ui.add(egui::TextEdit::singleline(model).hint_text(field.default));
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+
+                let can_run = !self.is_processing && !self.file_queue.is_empty();
+                if ui.add_enabled(can_run, egui::Button::new(self.i18n.t("start_compare"))).clicked() {
+                    run = true;
+                }
+            });
+
+        self.show_compare_setup = open;
+        if run {
+            self.start_comparison(ctx.clone());
+        }
+    }
+
+    fn start_comparison(&mut self, ctx: egui::Context) {
+        let Some(input) = self.file_queue.get(self.compare_file).cloned() else { return };
+
+        let mut sides = Vec::new();
+        for (provider_id, model) in &self.compare_sides {
+            // Apply the model override on a copy of the settings
+            let mut settings = self.config.provider_settings.clone();
+            let model = model.trim();
+            if !model.is_empty() {
+                settings.entry(provider_id.clone()).or_default().insert("model".to_string(), model.to_string());
+            }
+            match registry::build(provider_id, &settings) {
+                Ok(provider) => {
+                    let label = if model.is_empty() { provider_id.clone() } else { format!("{} / {}", provider_id, model) };
+                    sides.push(CompareSide { provider, label });
+                }
+                Err(e) => {
+                    self.status_message = format!("Error: {}", e);
+                    return;
+                }
+            }
+        }
+        let right = sides.pop().unwrap();
+        let left = sides.pop().unwrap();
+
+        self.is_processing = true;
+        self.total_progress = 0.0;
         let output_base = self.output_path.clone();
-        let ocr_prefix = self.i18n.t("ocr_result_dir").to_string();
+        let (tx, rx) = mpsc::channel(100);
+        self.receiver = Some(rx);
+
+        self.task = Some(tokio::spawn(async move {
+            match compare::run_comparison(left, right, &input, &output_base, &tx).await {
+                Ok(dir) => {
+                    let _ = tx.send(ProgressUpdate::Total(1.0)).await;
+                    let _ = tx.send(ProgressUpdate::Compared(dir)).await;
+                }
+                Err(e) => {
+                    let _ = tx.send(ProgressUpdate::Error(format!("Error: {}", e))).await;
+                }
+            }
+            ctx.request_repaint();
+        }));
+    }
 
+    fn start_batch(&mut self, ctx: egui::Context) {
+        let needs_settings = registry::find(&self.config.active_provider)
+            .map(|d| !d.missing_fields(&self.config.provider_settings).is_empty())
+            .unwrap_or(false);
+        if needs_settings {
+            self.open_settings_modal();
+            return;
+        }
+        let provider = match registry::build(&self.config.active_provider, &self.config.provider_settings) {
+            Ok(p) => p,
+            Err(e) => {
+                self.status_message = format!("Error: {}", e);
+                return;
+            }
+        };
+
+        let ocr_options = match Self::ocr_options(&self.config.output) {
+            Ok(o) => o,
+            Err(e) => {
+                self.status_message = format!("Error: {}", e);
+                return;
+            }
+        };
+
+        self.is_processing = true;
+        self.total_progress = 0.0;
+        let files = self.file_queue.clone();
+        let options = self.job_options();
         let (tx, rx) = mpsc::channel(100);
         self.receiver = Some(rx);
 
+        self.task = Some(tokio::spawn(async move {
+            match batch::submit(provider.as_ref(), &files, options, &ocr_options, &tx).await {
+                Ok(job) => {
+                    let _ = tx.send(ProgressUpdate::Total(1.0)).await;
+                    let _ = tx.send(ProgressUpdate::BatchSubmitted(Box::new(job))).await;
+                }
+                Err(e) => {
+                    let _ = tx.send(ProgressUpdate::Error(format!("Error: {}", e))).await;
+                }
+            }
+            ctx.request_repaint();
+        }));
+    }
+
+    // 处理后台查询的结果，并在需要时发起下一轮查询
+    fn handle_batch_updates(&mut self, ctx: &egui::Context) {
+        let mut changed = false;
+        while let Ok(update) = self.batch_rx.try_recv() {
+            match update {
+                BatchUpdate::State(id, state) => {
+                    self.batch_errors.remove(&id);
+                    self.batch_jobs.update_state(&id, state);
+                    changed = true;
+                }
+                BatchUpdate::Finished(id, files, job_error) => {
+                    if let Some(job) = self.batch_jobs.jobs.iter().find(|j| j.id == id) {
+                        let record = JobRecord::new(job.provider.clone(), job.options.clone(), job.submitted_at, files);
+                        self.last_output_dirs = record.output_dirs();
+                        self.status_message = match job_error {
+                            Some(e) => format!("Error: {}", e),
+                            None => format!("{}: {}", self.i18n.t("batch_completed"), id),
+                        };
+                        self.history.push(record);
+                        let _ = save_history(&self.history);
+                    }
+                    self.batch_errors.remove(&id);
+                    self.batch_jobs.update_state(&id, BatchState::Completed);
+                    changed = true;
+                }
+                BatchUpdate::Incomplete(id, saved, error) => {
+                    if let Some(job) = self.batch_jobs.jobs.iter_mut().find(|j| j.id == id) {
+                        job.saved = saved;
+                    }
+                    self.status_message = format!("{}: {}", self.i18n.t("batch_save_failed"), error);
+                    self.batch_errors.insert(id, error);
+                    changed = true;
+                }
+                BatchUpdate::Error(id, error) => {
+                    self.status_message = format!("{}: {}", self.i18n.t("batch_poll_failed"), error);
+                    self.batch_errors.insert(id, error);
+                }
+                BatchUpdate::PollDone => self.batch_polling = false,
+            }
+        }
+        if changed {
+            let _ = save_batch_jobs(&self.batch_jobs);
+        }
+
+        let pending: Vec<_> = self.batch_jobs.jobs.iter().filter(|j| j.is_pending()).cloned().collect();
+        if pending.is_empty() || self.batch_polling {
+            return;
+        }
+        let interval = Duration::from_secs(batch::POLL_INTERVAL_SECS);
+        if self.last_batch_poll.map(|t| t.elapsed() >= interval).unwrap_or(true) {
+            self.poll_batches(pending, ctx.clone());
+        }
+        ctx.request_repaint_after(interval);
+    }
+
+    fn poll_batches(&mut self, jobs: Vec<batch::BatchJob>, ctx: egui::Context) {
+        self.batch_polling = true;
+        self.last_batch_poll = Some(Instant::now());
+        let settings = self.config.provider_settings.clone();
+        let tx = self.batch_tx.clone();
         tokio::spawn(async move {
-            // Instantiate the correct provider
-            let provider: Box<dyn OcrProvider> = if provider_id == "mistral" {
-                Box::new(MistralProvider::new(api_key))
-            } else {
-                // Fallback or panic
-                let _ = tx.send(ProgressUpdate::Error(format!("Unknown provider: {}", provider_id))).await;
+            batch::poll_jobs(jobs, settings, tx).await;
+            ctx.request_repaint();
+        });
+    }
+
+    fn render_batch_jobs(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_batch;
+        let mut to_remove = None;
+        let mut refresh = false;
+
+        egui::Window::new(self.i18n.t("batch_jobs"))
+            .open(&mut open)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(self.i18n.t("batch_hint"));
+                    if ui.add_enabled(!self.batch_polling, egui::Button::new(self.i18n.t("refresh"))).clicked() {
+                        refresh = true;
+                    }
+                });
+                ui.separator();
+
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    for job in self.batch_jobs.jobs.iter().rev() {
+                        let state = match &job.state {
+                            BatchState::Pending { completed, total } => {
+                                format!("{} {}/{}", self.i18n.t("batch_pending"), completed, total)
+                            }
+                            BatchState::Completed => self.i18n.t("job_completed").to_string(),
+                            BatchState::Failed(e) => format!("{}: {}", self.i18n.t("job_failed"), e),
+                        };
+                        ui.horizontal(|ui| {
+                            ui.label(job.submitted_at_display());
+                            ui.label(&job.provider);
+                            ui.label(format!("{} {}", job.inputs().len(), self.i18n.t("files")));
+                            ui.label(state);
+                            if ui.button(self.i18n.t("remove_entry")).clicked() {
+                                to_remove = Some(job.id.clone());
+                            }
+                        });
+                        ui.label(egui::RichText::new(&job.id).small().weak());
+                        if let Some(error) = self.batch_errors.get(&job.id) {
+                            ui.colored_label(ui.visuals().error_fg_color, format!("{}: {}", self.i18n.t("batch_poll_failed"), error));
+                        }
+                        ui.separator();
+                    }
+                });
+            });
+
+        if refresh {
+            let pending: Vec<_> = self.batch_jobs.jobs.iter().filter(|j| j.is_pending()).cloned().collect();
+            if !pending.is_empty() {
+                self.poll_batches(pending, ctx.clone());
+            }
+        }
+        if let Some(id) = to_remove {
+            self.batch_errors.remove(&id);
+            self.batch_jobs.remove(&id);
+            let _ = save_batch_jobs(&self.batch_jobs);
+        }
+        self.show_batch = open;
+    }
+
+    fn request_start(&mut self, ctx: egui::Context) {
+        // Ask for the required settings (e.g. API key) of the current provider first
+        let needs_settings = registry::find(&self.config.active_provider)
+            .map(|d| !d.missing_fields(&self.config.provider_settings).is_empty())
+            .unwrap_or(false);
+        if needs_settings {
+            self.open_settings_modal();
+        } else {
+            self.start_processing(ctx, self.config.active_provider.clone(), self.file_queue.clone(), self.job_options());
+        }
+    }
+
+    fn start_processing(&mut self, ctx: egui::Context, provider_id: String, files: Vec<PathBuf>, job: JobOptions) {
+        let provider = match registry::build(&provider_id, &self.config.provider_settings) {
+            Ok(p) => p,
+            Err(e) => {
+                self.status_message = format!("Error: {}", e);
                 return;
-            };
-            
+            }
+        };
+
+        // Developer hook: record the provider responses to, or replay them from, a fixture directory
+        let provider: Box<dyn OcrProvider> = match (std::env::var_os("OCR_EG_RECORD"), std::env::var_os("OCR_EG_REPLAY")) {
+            (Some(dir), _) => Box::new(ReplayProvider::record(provider, dir.into())),
+            (None, Some(dir)) => Box::new(ReplayProvider::replay(dir.into(), provider_id.clone(), provider.model().map(str::to_string))),
+            _ => provider,
+        };
+
+        let ocr_options = match Self::ocr_options(&job.output) {
+            Ok(o) => o,
+            Err(e) => {
+                self.status_message = format!("Error: {}", e);
+                return;
+            }
+        };
+
+        self.is_processing = true;
+        self.last_output_dirs.clear();
+
+        self.running_job = Some(RunningJob {
+            provider: provider_id.clone(),
+            options: job.clone(),
+            started_at: chrono::Local::now().timestamp(),
+            inputs: files.clone(),
+            outcomes: Vec::new(),
+        });
+
+        let (tx, rx) = mpsc::channel(100);
+        self.receiver = Some(rx);
+
+        self.task = Some(tokio::spawn(async move {
             // Shared reference to provider
             let provider = Arc::new(provider);
 
             let total_files = files.len();
             let mut results = Vec::new();
 
+            // A failing file is recorded and the batch moves on, so it can be re-run from the history
             for (i, file_path) in files.iter().enumerate() {
                 let _ = tx.send(ProgressUpdate::Total((i as f32) / (total_files as f32))).await;
                 let _ = tx.send(ProgressUpdate::Message(format!("Processing {}...", file_path.file_name().unwrap_or_default().to_string_lossy()))).await;
                 
-                match process_single_file(provider.clone(), file_path, &output_base, &ocr_prefix, &tx).await {
-                    Ok(out_dir) => results.push(out_dir),
+                let outcome = match process_single_file(provider.clone(), file_path, &job, &ocr_options, &tx).await {
+                    Ok(out_dir) => FileOutcome { input: file_path.clone(), output_dir: Some(out_dir), error: None },
                     Err(e) => {
-                        let _ = tx.send(ProgressUpdate::Error(format!("Error: {}", e))).await;
-                        return;
+                        let _ = tx.send(ProgressUpdate::Message(format!("Error: {}", e))).await;
+                        FileOutcome { input: file_path.clone(), output_dir: None, error: Some(e.to_string()) }
                     }
-                }
+                };
+                results.push(outcome.clone());
+                let _ = tx.send(ProgressUpdate::FileDone(outcome)).await;
                 let _ = tx.send(ProgressUpdate::Current(1.0)).await;
             }
+
+            // 汇总结构化提取结果，按设置打包
+            let out_dirs: Vec<PathBuf> = results.iter().filter_map(|r| r.output_dir.clone()).collect();
+            if let Err(e) = pipeline::finish_job(&job, &out_dirs) {
+                let _ = tx.send(ProgressUpdate::Message(format!("Error: {}", e))).await;
+            }
             
             let _ = tx.send(ProgressUpdate::Total(1.0)).await;
             let _ = tx.send(ProgressUpdate::Finished(results)).await;
             ctx.request_repaint();
-        });
-    }
-}
-
-// Logic extracted and adapted for generic provider
-async fn process_single_file(
-    provider: Arc<Box<dyn OcrProvider>>,
-    path: &Path,
-    output_base: &Path,
-    ocr_prefix: &str,
-    tx: &mpsc::Sender<ProgressUpdate>
-) -> anyhow::Result<PathBuf> {
-    let mut actual_path = path.to_path_buf();
-    let is_img = pdf_utils::is_image_file(path);
-    let mut _temp_pdf_dir = None;
-
-    if is_img {
-        let _ = tx.send(ProgressUpdate::Message("Converting image to PDF...".into())).await;
-        let temp_dir = tempfile::tempdir()?;
-        let pdf_path = temp_dir.path().join("converted.pdf");
-        pdf_utils::convert_image_to_pdf(path, &pdf_path)?;
-        actual_path = pdf_path;
-        _temp_pdf_dir = Some(temp_dir);
+        }));
     }
-
-    let file_stem = path.file_stem().unwrap_or_default().to_string_lossy();
-    let out_dir = output_base.join(format!("{}{}", ocr_prefix, file_stem));
-    std::fs::create_dir_all(&out_dir)?;
-
-    let size_mb = pdf_utils::get_pdf_size_mb(&actual_path)?;
-    if size_mb <= 45.0 {
-        process_chunk(provider, &actual_path, &out_dir, 0, tx).await?;
-    } else {
-        let _ = tx.send(ProgressUpdate::Message("Splitting large PDF...".into())).await;
-        let (chunks, _temp_dir) = pdf_utils::split_pdf(&actual_path, 45.0)?;
-        let mut partial_files = Vec::new();
-        let mut page_offset = 0;
-        
-        for (i, chunk) in chunks.iter().enumerate() {
-            let _ = tx.send(ProgressUpdate::Message(format!("Processing chunk {}/{}:..", i+1, chunks.len()))).await;
-            let partial_file = process_chunk(provider.clone(), chunk, &out_dir, page_offset, tx).await?;
-            partial_files.push(partial_file);
-            
-            let doc = ::lopdf::Document::load(chunk)?;
-            page_offset += doc.get_pages().len() as u32;
-        }
-        
-        merge_results(&out_dir, &partial_files)?;
-    }
-
-    Ok(out_dir)
-}
-
-async fn process_chunk(
-    provider: Arc<Box<dyn OcrProvider>>,
-    path: &Path,
-    out_dir: &Path,
-    page_offset: u32,
-    tx: &mpsc::Sender<ProgressUpdate>
-) -> anyhow::Result<PathBuf> {
-    let _ = tx.send(ProgressUpdate::Current(0.1)).await;
-    // The provider interface handles the complex steps (upload, sign, ocr) internally
-    let result = provider.process_file(path).await?;
-    let _ = tx.send(ProgressUpdate::Current(0.9)).await;
-    
-    let partial_md = save_ocr_results(result, out_dir, page_offset)?;
-    Ok(partial_md)
-}
-
-fn save_ocr_results(ocr_result: OcrResult, out_dir: &Path, page_offset: u32) -> anyhow::Result<PathBuf> {
-    let images_dir = out_dir.join("images");
-    std::fs::create_dir_all(&images_dir)?;
-    
-    let mut page_markdowns = Vec::new();
-    for (i, page) in ocr_result.pages.into_iter().enumerate() {
-        let mut md = page.markdown;
-        for img in page.images {
-             let data = if img.base64.contains(",") {
-                img.base64.split(',').nth(1).unwrap_or("")
-            } else {
-                &img.base64
-            };
-            
-            let bytes = general_purpose::STANDARD.decode(data)?;
-            let img_filename = format!("part{}_page{}_{}.png", page_offset, i, img.id);
-            std::fs::write(images_dir.join(&img_filename), bytes)?;
-            
-            // Replace in markdown
-            let old_placeholder = format!("![{}]({})", img.id, img.id);
-            let new_placeholder = format!("![{}](images/{})", img.id, img_filename);
-            md = md.replace(&old_placeholder, &new_placeholder);
-            
-            let old_placeholder_slash = format!("![{}](/{})", img.id, img.id);
-            md = md.replace(&old_placeholder_slash, &new_placeholder);
-        }
-        let actual_page = page_offset + i as u32 + 1;
-        page_markdowns.push(format!("## Page {}
-
-{}", actual_page, md));
-    }
-    
-    let partial_md_path = out_dir.join(format!("part_{}.md", page_offset));
-    std::fs::write(&partial_md_path, page_markdowns.join("\n\n"))?;
-    Ok(partial_md_path)
-}
-
-fn merge_results(out_dir: &Path, partial_files: &[PathBuf]) -> anyhow::Result<()> {
-    let mut complete_content = Vec::new();
-    let mut sorted_files = partial_files.to_vec();
-    sorted_files.sort();
-    
-    for file in sorted_files {
-        let content = std::fs::read_to_string(file)?;
-        complete_content.push(content);
-    }
-    
-    std::fs::write(out_dir.join("complete.md"), complete_content.join("\n\n"))?;
-    Ok(())
 }
 
 fn setup_custom_fonts(ctx: &egui::Context) {
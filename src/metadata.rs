@@ -0,0 +1,142 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use crate::pdf_utils;
+
+// 生成 Markdown 时是否写入 YAML front matter，以及用户自定义的字段
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrontMatterOptions {
+    pub enabled: bool,
+    #[serde(default)]
+    pub extra: Vec<(String, String)>,
+}
+
+// 处理时收集的文档元数据，保存在 result.json 中，重新导出时保持不变
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct FrontMatter {
+    // 源文件名
+    pub source: String,
+    pub source_sha256: String,
+    pub provider: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    pub processed_at: String,
+    // PDF 文档信息中的标题和作者
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub extra: Vec<(String, String)>,
+}
+
+const BUILTIN_KEYS: [&str; 8] = ["source", "source_sha256", "pages", "provider", "model", "processed_at", "title", "author"];
+
+impl FrontMatter {
+    pub fn collect(source: &Path, provider: &str, model: Option<&str>, options: &FrontMatterOptions) -> Result<Self> {
+        // 大文件不整个读入内存
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut std::fs::File::open(source)?, &mut hasher)?;
+        // 图片输入或损坏的 PDF 没有文档信息
+        let info = if pdf_utils::is_image_file(source) {
+            Default::default()
+        } else {
+            pdf_utils::read_pdf_info(source).unwrap_or_default()
+        };
+        Ok(Self {
+            source: source.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            source_sha256: format!("{:x}", hasher.finalize()),
+            provider: provider.to_string(),
+            model: model.map(str::to_string),
+            processed_at: chrono::Local::now().format("%Y-%m-%dT%H:%M:%S%:z").to_string(),
+            title: info.title,
+            author: info.author,
+            extra: options.extra.clone(),
+        })
+    }
+
+    pub fn render(&self, pages: usize) -> String {
+        let mut lines = vec!["---".to_string()];
+        let mut push = |key: &str, value: String| lines.push(format!("{}: {}", yaml_key(key), value));
+        push("source", yaml_string(&self.source));
+        push("source_sha256", yaml_string(&self.source_sha256));
+        push("pages", pages.to_string());
+        push("provider", yaml_string(&self.provider));
+        if let Some(model) = &self.model {
+            push("model", yaml_string(model));
+        }
+        push("processed_at", yaml_string(&self.processed_at));
+        if let Some(title) = &self.title {
+            push("title", yaml_string(title));
+        }
+        if let Some(author) = &self.author {
+            push("author", yaml_string(author));
+        }
+        // 与内置字段或前面的字段重名的自定义字段会被忽略，保证 YAML 合法
+        let mut seen: Vec<&str> = BUILTIN_KEYS.to_vec();
+        for (key, value) in &self.extra {
+            let key = key.trim();
+            if key.is_empty() || seen.contains(&key) {
+                continue;
+            }
+            seen.push(key);
+            push(key, yaml_string(value));
+        }
+        lines.push("---".to_string());
+        lines.join("\n") + "\n\n"
+    }
+}
+
+fn yaml_key(key: &str) -> String {
+    if key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        key.to_string()
+    } else {
+        yaml_string(key)
+    }
+}
+
+// 统一写为双引号字符串，避免值被解析为数字、布尔值或日期
+fn yaml_string(value: &str) -> String {
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn front_matter_is_quoted_and_skips_duplicate_keys() {
+        let front_matter = FrontMatter {
+            source: "report.pdf".into(),
+            source_sha256: "abc".into(),
+            provider: "mistral".into(),
+            model: Some("mistral-ocr-latest".into()),
+            processed_at: "2024-01-02T03:04:05+08:00".into(),
+            title: Some("Q3 \"final\"".into()),
+            author: None,
+            extra: vec![("project".into(), "yes".into()), ("title".into(), "x".into()), ("team name".into(), "ops".into())],
+        };
+
+        let yaml = front_matter.render(3);
+
+        assert!(yaml.starts_with("---\nsource: \"report.pdf\"\n"));
+        assert!(yaml.contains("pages: 3\n"));
+        assert!(yaml.contains("title: \"Q3 \\\"final\\\"\"\n"));
+        assert!(!yaml.contains("author"));
+        assert!(yaml.contains("project: \"yes\"\n\"team name\": \"ops\"\n---\n\n"));
+        assert_eq!(yaml.matches("title:").count(), 1);
+    }
+}
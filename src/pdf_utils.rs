@@ -11,7 +11,7 @@ pub fn get_pdf_size_mb<P: AsRef<Path>>(path: P) -> Result<f64> {
 
 pub fn split_pdf<P: AsRef<Path>>(path: P, max_size_mb: f64) -> Result<(Vec<PathBuf>, PathBuf)> {
     let path = path.as_ref();
-    let temp_dir = tempfile::tempdir()?.into_path();
+    let temp_dir = tempfile::tempdir()?.keep();
 
     let doc = ::lopdf::Document::load(path)?;
     let total_pages = doc.get_pages().len() as u32;
@@ -95,3 +95,105 @@ pub fn is_image_file<P: AsRef<Path>>(path: P) -> bool {
     let ext = path.as_ref().extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
     matches!(ext.as_str(), "jpg" | "jpeg" | "png" | "bmp" | "tiff" | "tif")
 }
+
+// 提取每一页中最大的光栅图像（适用于扫描件等纯图片 PDF）
+pub fn extract_page_images<P: AsRef<Path>>(path: P) -> Result<Vec<::image::DynamicImage>> {
+    let doc = ::lopdf::Document::load(path.as_ref())?;
+    let mut images = Vec::new();
+
+    for (page_num, page_id) in doc.get_pages() {
+        let page_images = doc.get_page_images(page_id).unwrap_or_default();
+        let largest = page_images.iter()
+            .max_by_key(|img| img.width * img.height)
+            .ok_or_else(|| anyhow::anyhow!("Page {} contains no raster image", page_num))?;
+        let image = decode_pdf_image(largest)
+            .map_err(|e| anyhow::anyhow!("Page {}: {}", page_num, e))?;
+        images.push(image);
+    }
+
+    Ok(images)
+}
+
+fn decode_pdf_image(img: &::lopdf::xobject::PdfImage) -> Result<::image::DynamicImage> {
+    let filters = img.filters.clone().unwrap_or_default();
+    let data = match filters.first().map(|f| f.as_str()) {
+        Some("DCTDecode") | Some("JPXDecode") => return Ok(::image::load_from_memory(img.content)?),
+        Some("FlateDecode") => {
+            let mut decoded = Vec::new();
+            std::io::Read::read_to_end(&mut flate2::read::ZlibDecoder::new(img.content), &mut decoded)?;
+            decoded
+        }
+        None => img.content.to_vec(),
+        Some(other) => return Err(anyhow::anyhow!("Unsupported image filter {}", other)),
+    };
+
+    if img.bits_per_component.unwrap_or(8) != 8 {
+        return Err(anyhow::anyhow!("Unsupported bits per component"));
+    }
+
+    let (width, height) = (img.width as u32, img.height as u32);
+    let image = match img.color_space.as_deref() {
+        Some("DeviceRGB") => ::image::RgbImage::from_raw(width, height, data).map(::image::DynamicImage::ImageRgb8),
+        Some("DeviceGray") => ::image::GrayImage::from_raw(width, height, data).map(::image::DynamicImage::ImageLuma8),
+        other => return Err(anyhow::anyhow!("Unsupported color space {:?}", other)),
+    };
+    image.ok_or_else(|| anyhow::anyhow!("Image data does not match its dimensions"))
+}
+
+// 将输入文件转为逐页图像：图片文件本身即为一页，PDF 则提取每页的光栅图像
+pub fn load_page_images<P: AsRef<Path>>(path: P) -> Result<Vec<::image::DynamicImage>> {
+    if is_image_file(&path) {
+        Ok(vec![::image::open(path.as_ref())?])
+    } else {
+        extract_page_images(path)
+    }
+}
+
+// PDF 文档信息字典中的标题和作者
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PdfInfo {
+    pub title: Option<String>,
+    pub author: Option<String>,
+}
+
+pub fn read_pdf_info<P: AsRef<Path>>(path: P) -> Result<PdfInfo> {
+    let doc = ::lopdf::Document::load(path)?;
+    let info = match doc.trailer.get(b"Info") {
+        Ok(::lopdf::Object::Reference(id)) => doc.get_object(*id).ok(),
+        Ok(other) => Some(other),
+        Err(_) => None,
+    };
+    let Some(info) = info.and_then(|i| i.as_dict().ok()) else { return Ok(PdfInfo::default()) };
+    let field = |key: &[u8]| {
+        let text = decode_pdf_text(info.get(key).ok()?.as_str().ok()?);
+        let text = text.trim();
+        (!text.is_empty()).then(|| text.to_string())
+    };
+    Ok(PdfInfo { title: field(b"Title"), author: field(b"Author") })
+}
+
+// PDF 书签 (大纲)，按出现顺序；没有书签时返回空列表
+pub fn read_pdf_outline<P: AsRef<Path>>(path: P) -> Result<Vec<crate::toc::OutlineEntry>> {
+    let doc = ::lopdf::Document::load(path)?;
+    // lopdf 遇到不规范的书签会 panic，视为没有书签
+    let toc = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| doc.get_toc())) {
+        Ok(Ok(toc)) => toc,
+        _ => return Ok(Vec::new()),
+    };
+    Ok(toc.toc.into_iter()
+        .filter(|t| !t.title.trim().is_empty())
+        .map(|t| crate::toc::OutlineEntry { level: t.level, title: t.title.trim().to_string(), page: t.page as u32 })
+        .collect())
+}
+
+// PDF 文本字符串：带 BOM 的 UTF-16BE / UTF-8，否则按 PDFDocEncoding (近似 Latin-1) 解码
+fn decode_pdf_text(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = utf16.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+        return String::from_utf16_lossy(&units);
+    }
+    if let Some(utf8) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        return String::from_utf8_lossy(utf8).to_string();
+    }
+    bytes.iter().map(|&b| b as char).collect()
+}
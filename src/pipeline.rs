@@ -0,0 +1,627 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use crate::batch::BatchJob;
+use crate::bundle::{self, BundleMode};
+use crate::cleanup;
+use crate::export::{self, DocumentAnnotations, ExportFormat, PageMarker, SavedDocument, SavedPage};
+use crate::history::{FileOutcome, JobOptions};
+use crate::images::ImageStore;
+use crate::layout::DocumentTarget;
+use crate::metadata::FrontMatter;
+use crate::pdf_utils;
+use crate::providers::{OcrOptions, OcrProvider, OcrResult};
+use crate::providers::registry::Capabilities;
+
+pub enum ProgressUpdate {
+    Total(f32),
+    Current(f32),
+    Message(String),
+    // 一个文件处理完成 (成功或失败)，取消任务时据此记录已完成的部分
+    FileDone(FileOutcome),
+    Finished(Vec<FileOutcome>),
+    // 比较模式完成，附带比较结果目录
+    Compared(PathBuf),
+    // 批量任务已提交，等待后台查询结果
+    BatchSubmitted(Box<BatchJob>),
+    // 历史记录中的结果已重新导出
+    Exported,
+    Error(String),
+}
+
+// 供应商的一次调用单元：整个文件，或大 PDF 拆分后的一个分块
+pub struct InputChunk {
+    pub path: PathBuf,
+    // 该分块之前的页数
+    pub page_offset: u32,
+}
+
+pub struct PreparedInput {
+    pub chunks: Vec<InputChunk>,
+    // 图片转换出的临时 PDF，处理完成前需保持存在
+    _temp_pdf_dir: Option<tempfile::TempDir>,
+}
+
+// 按供应商的能力准备输入：必要时把图片转换为 PDF，并拆分超过 45MB 的 PDF
+pub async fn prepare_input(
+    path: &Path,
+    capabilities: Capabilities,
+    tx: &mpsc::Sender<ProgressUpdate>
+) -> anyhow::Result<PreparedInput> {
+    let mut actual_path = path.to_path_buf();
+    let is_img = pdf_utils::is_image_file(path);
+    let mut _temp_pdf_dir = None;
+
+    // Providers that take images directly get the original file
+    if is_img && !capabilities.accepts_images {
+        let _ = tx.send(ProgressUpdate::Message("Converting image to PDF...".into())).await;
+        let temp_dir = tempfile::tempdir()?;
+        let pdf_path = temp_dir.path().join("converted.pdf");
+        pdf_utils::convert_image_to_pdf(path, &pdf_path)?;
+        actual_path = pdf_path;
+        _temp_pdf_dir = Some(temp_dir);
+    }
+
+    let size_mb = pdf_utils::get_pdf_size_mb(&actual_path)?;
+    if size_mb <= 45.0 || is_img {
+        return Ok(PreparedInput {
+            chunks: vec![InputChunk { path: actual_path, page_offset: 0 }],
+            _temp_pdf_dir,
+        });
+    }
+
+    let _ = tx.send(ProgressUpdate::Message("Splitting large PDF...".into())).await;
+    let (paths, _temp_dir) = pdf_utils::split_pdf(&actual_path, 45.0)?;
+    let mut chunks = Vec::new();
+    let mut page_offset = 0;
+    for path in paths {
+        let doc = ::lopdf::Document::load(&path)?;
+        let pages = doc.get_pages().len() as u32;
+        chunks.push(InputChunk { path, page_offset });
+        page_offset += pages;
+    }
+    Ok(PreparedInput { chunks, _temp_pdf_dir })
+}
+
+// 一个文档的输出位置，以及各分块共享的结构化提取结果和按内容去重的图片
+pub struct DocumentOutput {
+    pub source: PathBuf,
+    pub out_dir: PathBuf,
+    // 导出文件名 (不含扩展名)
+    pub output_name: String,
+    pub annotations: DocumentAnnotations,
+    pub images: ImageStore,
+    // 保留分块 Markdown 的目录
+    pub debug_dir: Option<PathBuf>,
+    pub page_marker: PageMarker,
+    pub stitch_pages: bool,
+}
+
+impl DocumentOutput {
+    pub fn new(source: &Path, target: &DocumentTarget, options: &JobOptions) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(&target.dir)?;
+        Ok(Self {
+            source: source.to_path_buf(),
+            out_dir: target.dir.clone(),
+            output_name: target.output_name.clone(),
+            annotations: DocumentAnnotations { source: source.to_path_buf(), ..Default::default() },
+            images: ImageStore::new(&target.dir, options.output.image_export, options.output.layout.images),
+            debug_dir: options.output.keep_chunk_files.then(|| target.dir.join(export::DEBUG_DIR)),
+            page_marker: options.output.page_marker.clone(),
+            stitch_pages: options.output.stitch_pages,
+        })
+    }
+}
+
+// Logic extracted and adapted for generic provider
+pub async fn process_single_file(
+    provider: Arc<Box<dyn OcrProvider>>,
+    path: &Path,
+    job: &JobOptions,
+    options: &OcrOptions,
+    tx: &mpsc::Sender<ProgressUpdate>
+) -> anyhow::Result<PathBuf> {
+    let target = job.output.layout.resolve(&job.output_base, path, provider.id())?;
+    if target.skip {
+        let _ = tx.send(ProgressUpdate::Message(format!("Skipped, output exists: {}", target.dir.display()))).await;
+        return Ok(target.dir);
+    }
+    let prepared = prepare_input(path, provider.capabilities(), tx).await?;
+
+    let mut pages = Vec::new();
+    let mut output = DocumentOutput::new(path, &target, job)?;
+    let chunk_count = prepared.chunks.len();
+    for (i, chunk) in prepared.chunks.iter().enumerate() {
+        if chunk_count > 1 {
+            let _ = tx.send(ProgressUpdate::Message(format!("Processing chunk {}/{}:..", i+1, chunk_count))).await;
+        }
+        let result = process_chunk(provider.as_ref().as_ref(), &chunk.path, options, tx).await?;
+        pages.extend(save_ocr_results(result, provider.id(), chunk.page_offset, &mut output)?);
+    }
+
+    finish_document(&output, provider.id(), provider.model(), pages, job)?;
+    Ok(output.out_dir)
+}
+
+// 所有分块保存后：写入 result.json，并导出 Markdown 和其他选中的格式；
+// 无论是否拆分处理，每个文档得到的文件都相同
+pub fn finish_document(
+    output: &DocumentOutput,
+    provider_id: &str,
+    model: Option<&str>,
+    mut pages: Vec<SavedPage>,
+    job: &JobOptions,
+) -> anyhow::Result<()> {
+    let out_dir = output.out_dir.as_path();
+    pages.sort_by_key(|p| p.number);
+    if job.output.strip_headers {
+        cleanup::strip_headers_footers(&mut pages);
+    }
+    if !output.annotations.is_empty() {
+        output.annotations.save(out_dir)?;
+    }
+
+    // 源文件已被移走等情况下不写 front matter，识别结果照常保存
+    let front_matter = job.output.front_matter.enabled
+        .then(|| FrontMatter::collect(&output.source, provider_id, model, &job.output.front_matter).ok())
+        .flatten();
+    // 没有书签或无法读取时按识别出的标题生成目录
+    let outline = if job.output.toc && job.output.toc_from_outline && !pdf_utils::is_image_file(&output.source) {
+        pdf_utils::read_pdf_outline(&output.source).unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    let doc = SavedDocument {
+        source: output.source.clone(),
+        provider: provider_id.to_string(),
+        pages,
+        output_name: output.output_name.clone(),
+        front_matter,
+        page_marker: output.page_marker.clone(),
+        stitch_pages: output.stitch_pages,
+        toc: job.output.toc,
+        outline,
+        chunking: job.output.chunking,
+    };
+    doc.save(out_dir)?;
+
+    // Markdown is always produced
+    let mut formats = vec![ExportFormat::Markdown];
+    formats.extend(job.output.export_formats.iter().copied().filter(|f| *f != ExportFormat::Markdown));
+    export::export_document(&doc, out_dir, &formats)?;
+
+    if job.output.bundle == BundleMode::PerDocument {
+        bundle::bundle_document(out_dir)?;
+    }
+    Ok(())
+}
+
+async fn process_chunk(
+    provider: &dyn OcrProvider,
+    path: &Path,
+    options: &OcrOptions,
+    tx: &mpsc::Sender<ProgressUpdate>
+) -> anyhow::Result<OcrResult> {
+    let _ = tx.send(ProgressUpdate::Current(0.1)).await;
+    let _ = tx.send(ProgressUpdate::Message(format!("{}: {}...", provider.name(), path.file_name().unwrap_or_default().to_string_lossy()))).await;
+    // The provider interface handles the complex steps (upload, sign, ocr) internally
+    let result = provider.process_file(path, options).await?;
+    let _ = tx.send(ProgressUpdate::Current(0.9)).await;
+    Ok(result)
+}
+
+pub fn save_ocr_results(
+    ocr_result: OcrResult,
+    provider_id: &str,
+    page_offset: u32,
+    output: &mut DocumentOutput,
+) -> anyhow::Result<Vec<SavedPage>> {
+    let page_provider = ocr_result.provider.clone().unwrap_or_else(|| provider_id.to_string());
+    output.annotations.document.extend(ocr_result.document_annotation);
+    
+    let mut saved_pages = Vec::new();
+    for page in ocr_result.pages {
+        // 使用供应商返回的页面索引，跳过的页面不会导致后续页码错位
+        let number = page.global_number(page_offset);
+        let mut md = page.markdown;
+        let mut image_files = Vec::new();
+        let mut image_boxes = std::collections::BTreeMap::new();
+        for img in page.images {
+            // 扩展名按图片的实际格式确定，相同的图片复用已保存的文件
+            let id_stem = Path::new(&img.id).file_stem().unwrap_or_default().to_string_lossy();
+            let image_path = output.images.save(&img.base64, &format!("page{}_{}", number, id_stem))?;
+            
+            // Replace in markdown
+            let old_placeholder = format!("![{}]({})", img.id, img.id);
+            let new_placeholder = format!("![{}]({})", img.id, image_path);
+            md = md.replace(&old_placeholder, &new_placeholder);
+            
+            let old_placeholder_slash = format!("![{}](/{})", img.id, img.id);
+            md = md.replace(&old_placeholder_slash, &new_placeholder);
+            if let Some(bbox) = img.bbox {
+                image_boxes.insert(image_path.clone(), bbox);
+            }
+            if let Some(annotation) = img.annotation {
+                output.annotations.images.insert(image_path.clone(), annotation);
+            }
+            image_files.push(image_path);
+        }
+        saved_pages.push(SavedPage {
+            number,
+            markdown: md,
+            images: image_files,
+            provider: page_provider.clone(),
+            dimensions: page.dimensions,
+            image_boxes,
+        });
+    }
+    
+    if let Some(debug_dir) = &output.debug_dir {
+        std::fs::create_dir_all(debug_dir)?;
+        std::fs::write(debug_dir.join(format!("part_{}.md", page_offset)), export::render_pages(&saved_pages, &output.page_marker, output.stitch_pages))?;
+    }
+    Ok(saved_pages)
+}
+
+// 一次任务的所有文档保存后：汇总结构化提取结果，按设置整批打包
+pub fn finish_job(job: &JobOptions, out_dirs: &[PathBuf]) -> anyhow::Result<()> {
+    write_job_annotations(&job.output_base, out_dirs)?;
+    if job.output.bundle == BundleMode::PerJob {
+        bundle::bundle_job(&job.output_base, out_dirs)?;
+    }
+    Ok(())
+}
+
+// 汇总一次任务中所有文档的结构化提取结果，写入输出目录下带时间戳的 CSV
+pub fn write_job_annotations(output_base: &Path, out_dirs: &[PathBuf]) -> anyhow::Result<Option<PathBuf>> {
+    let documents: Vec<DocumentAnnotations> = out_dirs.iter()
+        .filter_map(|dir| DocumentAnnotations::load(dir).ok())
+        .filter(|a| !a.document.is_empty())
+        .collect();
+    if documents.is_empty() {
+        return Ok(None);
+    }
+    let path = output_base.join(format!("annotations_{}.csv", chrono::Local::now().format("%Y%m%d_%H%M%S")));
+    export::write_annotations_csv(&path, &documents)?;
+    Ok(Some(path))
+}
+
+pub fn re_export(out_dirs: &[PathBuf], format: ExportFormat) -> anyhow::Result<()> {
+    for dir in out_dirs {
+        let doc = SavedDocument::load(dir)?;
+        export::export_document(&doc, dir, &[format])?;
+        bundle::refresh_document_bundle(dir)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OutputOptions;
+    use crate::providers::mock::{MockFailure, MockProvider};
+
+    fn write_test_image(dir: &Path) -> PathBuf {
+        let path = dir.join("scan.png");
+        ::image::RgbImage::from_pixel(4, 4, ::image::Rgb([255, 255, 255])).save(&path).unwrap();
+        path
+    }
+
+    fn target(dir: &Path) -> DocumentTarget {
+        DocumentTarget { dir: dir.to_path_buf(), output_name: "complete".into(), skip: false }
+    }
+
+    async fn run(provider: Box<dyn OcrProvider>, input: &Path, output_base: &Path, formats: &[ExportFormat]) -> anyhow::Result<PathBuf> {
+        // 接收端被丢弃，进度消息会被直接忽略
+        let job = JobOptions {
+            output_base: output_base.to_path_buf(),
+            output: OutputOptions {
+                export_formats: formats.to_vec(),
+                ..Default::default()
+            },
+        };
+        let (tx, _) = mpsc::channel(1);
+        process_single_file(Arc::new(provider), input, &job, &OcrOptions::default(), &tx).await
+    }
+
+    #[tokio::test]
+    async fn image_input_writes_markdown_images_and_document() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = write_test_image(dir.path());
+        let provider = MockProvider::new(vec![MockProvider::simple_result(1, true)]);
+
+        let out_dir = run(Box::new(provider), &input, dir.path(), &[]).await.unwrap();
+
+        assert_eq!(out_dir, dir.path().join("ocr_scan"));
+        // 未拆分的文档同样得到最终的 complete.md，不留下中间文件
+        let md = std::fs::read_to_string(out_dir.join("complete.md")).unwrap();
+        assert!(!out_dir.join("part_0.md").exists());
+        assert!(md.starts_with("## Page 1\n\nText of page 0"));
+        assert!(md.contains("![img-0.jpeg](images/page1_img-0.png)"));
+        assert!(out_dir.join("images/page1_img-0.png").exists());
+
+        let doc = SavedDocument::load(&out_dir).unwrap();
+        assert_eq!(doc.provider, "mock");
+        assert_eq!(doc.pages.len(), 1);
+        assert_eq!(doc.pages[0].images, vec!["images/page1_img-0.png".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn pdf_input_numbers_pages_and_exports_html() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = write_test_image(dir.path());
+        let input = dir.path().join("doc.pdf");
+        pdf_utils::convert_image_to_pdf(&image, &input).unwrap();
+        let provider = MockProvider::new(vec![MockProvider::simple_result(3, false)]);
+
+        let out_dir = run(Box::new(provider), &input, dir.path(), &[ExportFormat::Markdown, ExportFormat::Html]).await.unwrap();
+
+        let md = std::fs::read_to_string(out_dir.join("complete.md")).unwrap();
+        assert!(md.contains("## Page 1") && md.contains("## Page 2") && md.contains("## Page 3"));
+        let html = std::fs::read_to_string(out_dir.join("complete.html")).unwrap();
+        assert!(html.contains("<h2>Page 3</h2>"));
+    }
+
+    #[tokio::test]
+    async fn provider_failure_is_returned() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = write_test_image(dir.path());
+        let provider = MockProvider::new(vec![]).with_failure(MockFailure::Always("quota exceeded".into()));
+
+        let err = run(Box::new(provider), &input, dir.path(), &[]).await.unwrap_err();
+        assert!(err.to_string().contains("quota exceeded"));
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_retried_on_the_backup_provider() {
+        use crate::providers::fallback::FallbackProvider;
+        use std::sync::atomic::Ordering;
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = write_test_image(dir.path());
+        let mut backup_result = MockProvider::simple_result(1, false);
+        backup_result.pages[0].markdown = "Text from backup".into();
+        let primary = MockProvider::new(vec![MockProvider::simple_result(1, false)])
+            .with_id("primary")
+            .with_failure(MockFailure::FirstCalls(1, "503".into()));
+        let backup = MockProvider::new(vec![backup_result]).with_id("backup");
+        let (primary_calls, backup_calls) = (primary.call_counter(), backup.call_counter());
+        let provider: Arc<Box<dyn OcrProvider>> = Arc::new(Box::new(FallbackProvider::new(vec![Box::new(primary), Box::new(backup)])));
+
+        let (tx, _) = mpsc::channel(1);
+        let process = |name: &str| {
+            let job = JobOptions { output_base: dir.path().join(name), ..Default::default() };
+            let (provider, input, tx) = (provider.clone(), input.clone(), tx.clone());
+            async move { process_single_file(provider, &input, &job, &OcrOptions::default(), &tx).await }
+        };
+
+        // 主供应商返回 503：由备用供应商重试一次，保存备用供应商的结果
+        let out_dir = process("first").await.unwrap();
+        assert_eq!((primary_calls.load(Ordering::SeqCst), backup_calls.load(Ordering::SeqCst)), (1, 1));
+        assert!(std::fs::read_to_string(out_dir.join("complete.md")).unwrap().contains("Text from backup"));
+        assert_eq!(SavedDocument::load(&out_dir).unwrap().pages[0].provider, "backup");
+
+        // 主供应商恢复后不再调用备用供应商
+        let out_dir = process("second").await.unwrap();
+        assert_eq!((primary_calls.load(Ordering::SeqCst), backup_calls.load(Ordering::SeqCst)), (2, 1));
+        assert!(std::fs::read_to_string(out_dir.join("complete.md")).unwrap().contains("Text of page 0"));
+        assert_eq!(SavedDocument::load(&out_dir).unwrap().pages[0].provider, "primary");
+    }
+
+    #[tokio::test]
+    async fn fallback_records_the_provider_of_each_page() {
+        use crate::providers::fallback::FallbackProvider;
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = write_test_image(dir.path());
+        // 主供应商第一次调用返回 503，之后恢复
+        let primary = MockProvider::new(vec![MockProvider::simple_result(1, false)])
+            .with_id("primary")
+            .with_failure(MockFailure::FirstCalls(1, "503".into()));
+        let backup = MockProvider::new(vec![MockProvider::simple_result(1, false)]).with_id("backup");
+        let provider = FallbackProvider::new(vec![Box::new(primary), Box::new(backup)]);
+
+        // 两个分块：第一个由备用供应商完成，第二个回到主供应商
+        let first = provider.process_file(&input, &OcrOptions::default()).await.unwrap();
+        let second = provider.process_file(&input, &OcrOptions::default()).await.unwrap();
+        assert_eq!(first.provider.as_deref(), Some("backup"));
+        assert_eq!(second.provider.as_deref(), Some("primary"));
+
+        let job = JobOptions { output_base: dir.path().to_path_buf(), ..Default::default() };
+        let mut output = DocumentOutput::new(&input, &target(dir.path()), &job).unwrap();
+        let mut pages = save_ocr_results(first, "fallback", 0, &mut output).unwrap();
+        pages.extend(save_ocr_results(second, "fallback", 1, &mut output).unwrap());
+        finish_document(&output, "fallback", None, pages, &job).unwrap();
+
+        let doc = SavedDocument::load(dir.path()).unwrap();
+        assert_eq!(doc.provider, "fallback");
+        assert_eq!(doc.pages.iter().map(|p| p.provider.as_str()).collect::<Vec<_>>(), vec!["backup", "primary"]);
+    }
+
+    #[tokio::test]
+    async fn fallback_stops_on_non_retryable_errors() {
+        use crate::providers::fallback::FallbackProvider;
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = write_test_image(dir.path());
+        let broken = MockProvider::new(vec![]).with_failure(MockFailure::Always("invalid document".into()));
+        let backup = MockProvider::new(vec![MockProvider::simple_result(1, false)]).with_id("backup");
+        let provider = FallbackProvider::new(vec![Box::new(broken), Box::new(backup)]);
+
+        // 备用供应商没有被调用：否则会返回它的结果
+        let err = provider.process_file(&input, &OcrOptions::default()).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("invalid document"));
+        assert!(!format!("{:#}", err).contains("All providers failed"));
+    }
+
+    #[tokio::test]
+    async fn annotations_are_saved_per_document_and_as_csv() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = write_test_image(dir.path());
+        let mut result = MockProvider::simple_result(1, true);
+        result.document_annotation = Some(serde_json::json!({"invoice_no": "A-1", "total": 12.5, "note": "a, b"}));
+        result.pages[0].images[0].annotation = Some(serde_json::json!({"kind": "logo"}));
+
+        let out_dir = run(Box::new(MockProvider::new(vec![result])), &input, dir.path(), &[]).await.unwrap();
+
+        let annotations = DocumentAnnotations::load(&out_dir).unwrap();
+        assert_eq!(annotations.document.len(), 1);
+        assert_eq!(annotations.images["images/page1_img-0.png"]["kind"], "logo");
+
+        let csv_path = write_job_annotations(dir.path(), &[out_dir]).unwrap().unwrap();
+        let csv = std::fs::read_to_string(csv_path).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next().unwrap(), "source,invoice_no,note,total");
+        assert!(lines.next().unwrap().ends_with(",A-1,\"a, b\",12.5"));
+    }
+
+    #[tokio::test]
+    async fn bounding_boxes_and_dimensions_are_kept() {
+        use crate::providers::{BoundingBox, PageDimensions};
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = write_test_image(dir.path());
+        let mut result = MockProvider::simple_result(1, true);
+        result.pages[0].dimensions = Some(PageDimensions { dpi: 200, height: 2200, width: 1700 });
+        result.pages[0].images[0].bbox = Some(BoundingBox { top_left_x: 100, top_left_y: 200, bottom_right_x: 950, bottom_right_y: 600 });
+
+        let out_dir = run(Box::new(MockProvider::new(vec![result])), &input, dir.path(), &[ExportFormat::Html]).await.unwrap();
+
+        let doc = SavedDocument::load(&out_dir).unwrap();
+        assert_eq!(doc.pages[0].dimensions.unwrap().dpi, 200);
+        assert_eq!(doc.pages[0].image_boxes["images/page1_img-0.png"].bottom_right_x, 950);
+        let html = std::fs::read_to_string(out_dir.join("complete.html")).unwrap();
+        assert!(html.contains("src=\"images/page1_img-0.png\" alt=\"img-0.jpeg\" style=\"width:50.0%\" />"));
+    }
+
+    #[test]
+    fn identical_images_are_saved_once_and_transcoded() {
+        use crate::images::ImageExport;
+
+        let dir = tempfile::tempdir().unwrap();
+        let job = JobOptions {
+            output_base: dir.path().to_path_buf(),
+            output: OutputOptions {
+                image_export: ImageExport::Jpeg { quality: 80 },
+                ..Default::default()
+            },
+        };
+        let mut result = MockProvider::simple_result(2, true);
+        result.pages[0].images[0].base64 = format!("data:image/png;base64,{}", result.pages[0].images[0].base64);
+        let mut output = DocumentOutput::new(Path::new("doc.pdf"), &target(dir.path()), &job).unwrap();
+
+        let pages = save_ocr_results(result, "mock", 0, &mut output).unwrap();
+
+        assert_eq!(pages[0].images, vec!["images/page1_img-0.jpg".to_string()]);
+        assert_eq!(pages[1].images, pages[0].images);
+        assert!(pages[1].markdown.contains("![img-1.jpeg](images/page1_img-0.jpg)"));
+        assert_eq!(std::fs::read_dir(dir.path().join("images")).unwrap().count(), 1);
+        let bytes = std::fs::read(dir.path().join("images/page1_img-0.jpg")).unwrap();
+        assert_eq!(::image::guess_format(&bytes).unwrap(), ::image::ImageFormat::Jpeg);
+    }
+
+    #[test]
+    fn provider_page_indices_give_global_page_numbers() {
+        let dir = tempfile::tempdir().unwrap();
+        let job = JobOptions {
+            output_base: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        // 第二个分块 (前面有 10 页)，供应商跳过了索引 1 的空白页
+        let mut result = MockProvider::simple_result(3, true);
+        result.pages.remove(1);
+        let mut output = DocumentOutput::new(Path::new("doc.pdf"), &target(dir.path()), &job).unwrap();
+
+        let pages = save_ocr_results(result, "mock", 10, &mut output).unwrap();
+
+        assert_eq!(pages.iter().map(|p| p.number).collect::<Vec<_>>(), vec![11, 13]);
+        // 两页的图片内容相同，只保存一次
+        assert_eq!(pages[0].images, vec!["images/page11_img-0.png".to_string()]);
+        let md = export::render_pages(&pages, &PageMarker::Heading, false);
+        assert!(md.contains("## Page 13\n\nText of page 2"));
+    }
+
+    #[tokio::test]
+    async fn layout_template_names_outputs_and_flattens_images() {
+        use crate::layout::{ImageLayout, OutputLayout};
+
+        let dir = tempfile::tempdir().unwrap();
+        let job = JobOptions {
+            output_base: dir.path().to_path_buf(),
+            output: OutputOptions {
+                export_formats: vec![ExportFormat::Html],
+                layout: OutputLayout {
+                    path_template: "{provider}/{stem}/{stem}.md".into(),
+                    images: ImageLayout::Flat,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        };
+        let input = dir.path().join("doc.pdf");
+        std::fs::write(&input, b"%PDF-1.4").unwrap();
+        let provider: Box<dyn OcrProvider> = Box::new(MockProvider::new(vec![MockProvider::simple_result(1, true)]));
+        let (tx, _) = mpsc::channel(1);
+
+        let out_dir = process_single_file(Arc::new(provider), &input, &job, &OcrOptions::default(), &tx).await.unwrap();
+
+        assert_eq!(out_dir, dir.path().join("mock/doc"));
+        assert!(out_dir.join("page1_img-0.png").exists());
+        let html = std::fs::read_to_string(out_dir.join("doc.html")).unwrap();
+        assert!(html.contains("src=\"page1_img-0.png\""));
+        assert_eq!(SavedDocument::load(&out_dir).unwrap().output_name, "doc");
+    }
+
+    #[tokio::test]
+    async fn front_matter_is_written_to_markdown_only() {
+        use crate::metadata::FrontMatterOptions;
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = write_test_image(dir.path());
+        let job = JobOptions {
+            output_base: dir.path().to_path_buf(),
+            output: OutputOptions {
+                export_formats: vec![ExportFormat::Html],
+                front_matter: FrontMatterOptions { enabled: true, extra: vec![("project".into(), "archive".into())] },
+                ..Default::default()
+            },
+        };
+        let provider: Box<dyn OcrProvider> = Box::new(MockProvider::new(vec![MockProvider::simple_result(1, false)]));
+        let (tx, _) = mpsc::channel(1);
+
+        let out_dir = process_single_file(Arc::new(provider), &input, &job, &OcrOptions::default(), &tx).await.unwrap();
+
+        let md = std::fs::read_to_string(out_dir.join("complete.md")).unwrap();
+        assert!(md.starts_with("---\nsource: \"scan.png\"\nsource_sha256: \""));
+        assert!(md.contains("pages: 1\nprovider: \"mock\"\n"));
+        assert!(md.contains("project: \"archive\"\n---\n\n## Page 1"));
+        let html = std::fs::read_to_string(out_dir.join("complete.html")).unwrap();
+        assert!(!html.contains("source_sha256"));
+    }
+
+    #[test]
+    fn chunks_are_finished_in_page_order_with_debug_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let job = JobOptions {
+            output_base: dir.path().to_path_buf(),
+            output: OutputOptions {
+                export_formats: vec![ExportFormat::EmbeddedMarkdown],
+                keep_chunk_files: true,
+                ..Default::default()
+            },
+        };
+        let mut output = DocumentOutput::new(Path::new("doc.pdf"), &target(dir.path()), &job).unwrap();
+        let mut pages = save_ocr_results(MockProvider::simple_result(1, false), "mock", 10, &mut output).unwrap();
+        pages.extend(save_ocr_results(MockProvider::simple_result(1, true), "mock", 0, &mut output).unwrap());
+
+        finish_document(&output, "mock", None, pages, &job).unwrap();
+
+        let md = std::fs::read_to_string(dir.path().join("complete.md")).unwrap();
+        assert!(md.find("## Page 1").unwrap() < md.find("## Page 11").unwrap());
+        assert!(dir.path().join("debug/part_10.md").exists());
+        assert!(!dir.path().join("part_0.md").exists());
+        let embedded = std::fs::read_to_string(dir.path().join("complete.embedded.md")).unwrap();
+        assert!(embedded.contains("](data:image/png;base64,"));
+        assert!(!embedded.contains("](images/"));
+    }
+}
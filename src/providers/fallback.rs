@@ -0,0 +1,125 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use std::path::Path;
+use super::{OcrOptions, OcrProvider, OcrResult, PurgeReport, is_retryable};
+use super::registry::{Capabilities, ConfigField, FieldKind, ProviderDescriptor, ProviderSettings};
+
+// --- 注册信息 ---
+
+pub static DESCRIPTOR: ProviderDescriptor = ProviderDescriptor {
+    id: "fallback",
+    name: "Fallback Chain",
+    fields: &[
+        // 按顺序尝试的供应商 ID，以逗号分隔；各成员使用自己的设置
+        ConfigField { key: "chain", label: "field_chain", kind: FieldKind::Text, default: "mistral,tesseract", required: true },
+    ],
+    // 实际能力取决于链中的成员 (见 capabilities())，这里只列出保守的值
+    capabilities: Capabilities {
+        accepts_pdf: false,
+        accepts_images: false,
+        returns_images: false,
+        requires_network: true,
+        stores_uploads: false,
+        supports_batch: false,
+        supports_annotations: false,
+    },
+    help_url: None,
+    factory: build,
+};
+
+fn build(settings: &ProviderSettings) -> Result<Box<dyn OcrProvider>> {
+    let mut providers = Vec::new();
+    for id in settings.require("chain")?.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        if id == DESCRIPTOR.id {
+            return Err(anyhow::anyhow!("A fallback chain cannot contain itself"));
+        }
+        providers.push(settings.build_provider(id)?);
+    }
+    if providers.is_empty() {
+        return Err(anyhow::anyhow!("The fallback chain is empty"));
+    }
+    Ok(Box::new(FallbackProvider::new(providers)))
+}
+
+// --- Provider 实现 ---
+
+// 组合供应商：对每个文件 (或拆分后的每个分块) 依次尝试各供应商，
+// 遇到可重试的错误 (限流、服务端错误、服务不可达) 时换下一个
+pub struct FallbackProvider {
+    providers: Vec<Box<dyn OcrProvider>>,
+}
+
+impl FallbackProvider {
+    pub fn new(providers: Vec<Box<dyn OcrProvider>>) -> Self {
+        Self { providers }
+    }
+}
+
+#[async_trait]
+impl OcrProvider for FallbackProvider {
+    fn id(&self) -> &str {
+        DESCRIPTOR.id
+    }
+
+    fn name(&self) -> &str {
+        DESCRIPTOR.name
+    }
+
+    // 只有所有成员都支持的输入才直接传入
+    fn capabilities(&self) -> Capabilities {
+        let caps: Vec<Capabilities> = self.providers.iter().map(|p| p.capabilities()).collect();
+        Capabilities {
+            accepts_pdf: caps.iter().all(|c| c.accepts_pdf),
+            accepts_images: caps.iter().all(|c| c.accepts_images),
+            returns_images: caps.iter().any(|c| c.returns_images),
+            requires_network: caps.iter().any(|c| c.requires_network),
+            stores_uploads: caps.iter().any(|c| c.stores_uploads),
+            // 批量任务由单个供应商完成，不参与回退
+            supports_batch: false,
+            supports_annotations: caps.iter().any(|c| c.supports_annotations),
+        }
+    }
+
+    async fn process_file(&self, file_path: &Path, options: &OcrOptions) -> Result<OcrResult> {
+        let mut errors = Vec::new();
+        for provider in &self.providers {
+            match provider.process_file(file_path, options).await {
+                Ok(mut result) => {
+                    if result.provider.is_none() {
+                        result.provider = Some(provider.id().to_string());
+                    }
+                    return Ok(result);
+                }
+                Err(e) if is_retryable(&e) => {
+                    errors.push(format!("{}: {}", provider.name(), e));
+                }
+                Err(e) => return Err(e.context(format!("{} failed", provider.name()))),
+            }
+        }
+        Err(anyhow::anyhow!("All providers failed: {}", errors.join("; ")))
+    }
+
+    // 维护操作转发给会保存上传文件的成员
+    async fn list_uploads(&self) -> Result<Vec<String>> {
+        let mut uploads = Vec::new();
+        for provider in self.providers.iter().filter(|p| p.capabilities().stores_uploads) {
+            uploads.extend(provider.list_uploads().await?);
+        }
+        Ok(uploads)
+    }
+
+    async fn purge_uploads(&self, pending_batches: &[(String, String)]) -> Result<PurgeReport> {
+        let mut report = PurgeReport::default();
+        for provider in self.providers.iter().filter(|p| p.capabilities().stores_uploads) {
+            match provider.purge_uploads(pending_batches).await {
+                Ok(r) => {
+                    report.deleted += r.deleted;
+                    report.skipped += r.skipped;
+                    report.failed.extend(r.failed);
+                }
+                Err(e) => report.failed.push(format!("{}: {}", provider.name(), e)),
+            }
+        }
+        Ok(report)
+    }
+}
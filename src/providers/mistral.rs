@@ -1,9 +1,11 @@
 use async_trait::async_trait;
 use anyhow::Result;
 use reqwest::{Client, multipart};
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
-use super::{OcrProvider, OcrResult, OcrPage, OcrImage};
+use super::{BatchStatus, BoundingBox, OcrOptions, OcrProvider, OcrResult, OcrPage, OcrImage, PageDimensions, ProviderError, PurgeReport};
+use super::registry::{Capabilities, ConfigField, FieldKind, ProviderDescriptor, ProviderSettings};
 
 // --- Mistral API 特定的数据结构 (内部使用) ---
 #[derive(Debug, Serialize, Deserialize)]
@@ -11,6 +13,20 @@ struct FileResponse {
     id: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct FileListResponse {
+    data: Vec<FileInfo>,
+    #[serde(default)]
+    total: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FileInfo {
+    id: String,
+    #[serde(default)]
+    filename: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct SignedUrlResponse {
     url: String,
@@ -21,6 +37,24 @@ struct MistralImage {
     id: String,
     #[serde(rename = "image_base64")]
     image_base64: Option<String>,
+    // bbox 结构化提取的结果 (JSON 字符串)
+    #[serde(default)]
+    image_annotation: Option<String>,
+    top_left_x: Option<u32>,
+    top_left_y: Option<u32>,
+    bottom_right_x: Option<u32>,
+    bottom_right_y: Option<u32>,
+}
+
+impl MistralImage {
+    fn bbox(&self) -> Option<BoundingBox> {
+        Some(BoundingBox {
+            top_left_x: self.top_left_x?,
+            top_left_y: self.top_left_y?,
+            bottom_right_x: self.bottom_right_x?,
+            bottom_right_y: self.bottom_right_y?,
+        })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -28,11 +62,16 @@ struct MistralPage {
     index: u32,
     markdown: String,
     images: Vec<MistralImage>,
+    #[serde(default)]
+    dimensions: Option<PageDimensions>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct MistralResponse {
     pages: Vec<MistralPage>,
+    // 文档结构化提取的结果 (JSON 字符串)
+    #[serde(default)]
+    document_annotation: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -47,6 +86,140 @@ struct OcrRequest {
     model: String,
     document: DocumentUrl,
     include_image_base64: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    document_annotation_format: Option<ResponseFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bbox_annotation_format: Option<ResponseFormat>,
+}
+
+// 结构化提取的格式：{"type": "json_schema", "json_schema": {"name", "schema", "strict"}}
+#[derive(Debug, Serialize)]
+struct ResponseFormat {
+    #[serde(rename = "type")]
+    format_type: String,
+    json_schema: JsonSchema,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonSchema {
+    name: String,
+    schema: serde_json::Value,
+    strict: bool,
+}
+
+impl ResponseFormat {
+    fn new(name: &str, schema: &serde_json::Value) -> Self {
+        Self {
+            format_type: "json_schema".to_string(),
+            json_schema: JsonSchema {
+                // 优先使用 Schema 自带的 title 作为名称
+                name: schema.get("title").and_then(|t| t.as_str()).unwrap_or(name).to_string(),
+                schema: schema.clone(),
+                strict: true,
+            },
+        }
+    }
+}
+
+// --- Batch API ---
+
+// 批量任务输入 JSONL 中的一行；custom_id 为文件在提交列表中的序号
+#[derive(Debug, Serialize)]
+struct BatchRequestLine {
+    custom_id: String,
+    body: BatchOcrBody,
+}
+
+#[derive(Debug, Serialize)]
+struct BatchOcrBody {
+    document: DocumentUrl,
+    include_image_base64: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    document_annotation_format: Option<ResponseFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bbox_annotation_format: Option<ResponseFormat>,
+}
+
+#[derive(Debug, Serialize)]
+struct BatchJobRequest {
+    input_files: Vec<String>,
+    endpoint: String,
+    model: String,
+    metadata: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchJobResponse {
+    id: String,
+    status: String,
+    #[serde(default)]
+    input_files: Vec<String>,
+    output_file: Option<String>,
+    error_file: Option<String>,
+    #[serde(default)]
+    total_requests: usize,
+    #[serde(default)]
+    succeeded_requests: usize,
+    #[serde(default)]
+    failed_requests: usize,
+    #[serde(default)]
+    metadata: HashMap<String, String>,
+}
+
+// 输出文件中的一行；custom_id 在解析本结构之前单独读取
+#[derive(Debug, Deserialize)]
+struct BatchResultLine {
+    response: Option<BatchResultResponse>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchResultResponse {
+    status_code: u16,
+    body: serde_json::Value,
+}
+
+// 批量任务元数据中记录的已上传文档 ID (逗号分隔)，任务结束后据此清理
+const BATCH_DOCUMENTS_KEY: &str = "documents";
+// 同步识别的签名链接只在本次请求中使用
+const SIGNED_URL_EXPIRY_HOURS: u32 = 24;
+// 签名链接需要在批量任务完成前保持有效 (任务最长运行 24 小时)
+const BATCH_URL_EXPIRY_HOURS: u32 = 48;
+
+// --- 注册信息 ---
+
+pub static DESCRIPTOR: ProviderDescriptor = ProviderDescriptor {
+    id: "mistral",
+    name: "Mistral AI",
+    fields: &[
+        ConfigField { key: "api_key", label: "field_api_key", kind: FieldKind::Secret, default: "", required: true },
+        ConfigField { key: "base_url", label: "field_base_url", kind: FieldKind::Url, default: "https://api.mistral.ai", required: false },
+        ConfigField { key: "model", label: "field_model", kind: FieldKind::Text, default: "mistral-ocr-latest", required: false },
+        ConfigField { key: "include_images", label: "field_include_images", kind: FieldKind::Bool, default: "true", required: false },
+        // 默认在识别完成后删除上传到 Mistral 的文件
+        ConfigField { key: "keep_uploaded_files", label: "field_keep_uploaded_files", kind: FieldKind::Bool, default: "false", required: false },
+    ],
+    capabilities: Capabilities {
+        accepts_pdf: true,
+        accepts_images: false,
+        returns_images: true,
+        requires_network: true,
+        stores_uploads: true,
+        supports_batch: true,
+        supports_annotations: true,
+    },
+    help_url: Some("https://console.mistral.ai/"),
+    factory: build,
+};
+
+fn build(settings: &ProviderSettings) -> Result<Box<dyn OcrProvider>> {
+    Ok(Box::new(MistralProvider::new(
+        settings.require("api_key")?,
+        settings.get("base_url"),
+        settings.get("model"),
+        settings.get_bool("include_images"),
+        settings.get_bool("keep_uploaded_files"),
+    )))
 }
 
 // --- Provider 实现 ---
@@ -54,16 +227,114 @@ struct OcrRequest {
 pub struct MistralProvider {
     client: Client,
     api_key: String,
+    base_url: String,
+    model: String,
+    include_images: bool,
+    keep_uploaded_files: bool,
+}
+
+// 上传文件的清理守卫：正常流程中显式删除；若任务被取消 (future 被丢弃)，
+// 则在 Drop 时后台发起删除请求
+struct UploadGuard {
+    client: Client,
+    delete_url: String,
+    api_key: String,
+    armed: bool,
+}
+
+impl UploadGuard {
+    async fn delete(mut self) -> Result<()> {
+        self.armed = false;
+        delete_remote_file(&self.client, &self.delete_url, &self.api_key).await
+    }
+
+    // 文件需要保留 (如已交给批量任务使用)
+    fn disarm(mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for UploadGuard {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            let client = self.client.clone();
+            let url = std::mem::take(&mut self.delete_url);
+            let api_key = std::mem::take(&mut self.api_key);
+            handle.spawn(async move {
+                let _ = delete_remote_file(&client, &url, &api_key).await;
+            });
+        }
+    }
+}
+
+async fn delete_remote_file(client: &Client, url: &str, api_key: &str) -> Result<()> {
+    let response = client.delete(url)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status().as_u16();
+        let error_text = response.text().await?;
+        return Err(ProviderError::new(Some(status), format!("Mistral Delete failed: {}", error_text)).into());
+    }
+    Ok(())
 }
 
 impl MistralProvider {
-    pub fn new(api_key: String) -> Self {
+    pub fn new(api_key: String, base_url: String, model: String, include_images: bool, keep_uploaded_files: bool) -> Self {
         Self {
             client: Client::new(),
             api_key,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model,
+            include_images,
+            keep_uploaded_files,
         }
     }
 
+    fn upload_guard(&self, file_id: &str) -> UploadGuard {
+        UploadGuard {
+            client: self.client.clone(),
+            delete_url: format!("{}/v1/files/{}", self.base_url, file_id),
+            api_key: self.api_key.clone(),
+            armed: true,
+        }
+    }
+
+    // 列出账户中所有 purpose=ocr 的文件 (分页读取)
+    async fn list_ocr_files(&self) -> Result<Vec<FileInfo>> {
+        const PAGE_SIZE: usize = 100;
+        let mut files = Vec::new();
+        let mut page = 0;
+        loop {
+            let response = self.client.get(format!("{}/v1/files", self.base_url))
+                .header("Authorization", format!("Bearer {}", self.api_key))
+                .query(&[("purpose", "ocr".to_string()), ("page", page.to_string()), ("page_size", PAGE_SIZE.to_string())])
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                let status = response.status().as_u16();
+                let error_text = response.text().await?;
+                return Err(ProviderError::new(Some(status), format!("Mistral List files failed: {}", error_text)).into());
+            }
+
+            let res: FileListResponse = response.json().await?;
+            let count = res.data.len();
+            files.extend(res.data);
+            // total 缺省为 0 时视为未知，只按页大小判断是否结束
+            if count < PAGE_SIZE || (res.total > 0 && files.len() >= res.total) {
+                break;
+            }
+            page += 1;
+        }
+        Ok(files)
+    }
+
     async fn upload_file(&self, path: &Path) -> Result<FileResponse> {
         let filename = path.file_name()
             .and_then(|n| n.to_str())
@@ -71,114 +342,389 @@ impl MistralProvider {
             .to_string();
         
         let file_content = tokio::fs::read(path).await?;
-        let part = multipart::Part::bytes(file_content)
+        self.upload_bytes(filename, file_content, "ocr").await
+    }
+
+    async fn upload_bytes(&self, filename: String, content: Vec<u8>, purpose: &'static str) -> Result<FileResponse> {
+        let part = multipart::Part::bytes(content)
             .file_name(filename);
         
         let form = multipart::Form::new()
             .part("file", part)
-            .text("purpose", "ocr");
+            .text("purpose", purpose);
         
-        let response = self.client.post("https://api.mistral.ai/v1/files")
+        let response = self.client.post(format!("{}/v1/files", self.base_url))
             .header("Authorization", format!("Bearer {}", self.api_key))
             .multipart(form)
             .send()
             .await?;
         
         if !response.status().is_success() {
+            let status = response.status().as_u16();
             let error_text = response.text().await?;
-            return Err(anyhow::anyhow!("Mistral Upload failed: {}", error_text));
+            return Err(ProviderError::new(Some(status), format!("Mistral Upload failed: {}", error_text)).into());
         }
         
         Ok(response.json().await?)
     }
 
-    async fn get_signed_url(&self, file_id: &str) -> Result<String> {
-        let response = self.client.get(format!("https://api.mistral.ai/v1/files/{}/url", file_id))
+    async fn get_signed_url(&self, file_id: &str, expiry_hours: u32) -> Result<String> {
+        let response = self.client.get(format!("{}/v1/files/{}/url", self.base_url, file_id))
             .header("Authorization", format!("Bearer {}", self.api_key))
+            .query(&[("expiry", expiry_hours)])
             .send()
             .await?;
         
         if !response.status().is_success() {
+            let status = response.status().as_u16();
             let error_text = response.text().await?;
-            return Err(anyhow::anyhow!("Mistral Signed URL failed: {}", error_text));
+            return Err(ProviderError::new(Some(status), format!("Mistral Signed URL failed: {}", error_text)).into());
         }
         
         let res: SignedUrlResponse = response.json().await?;
         Ok(res.url)
     }
 
-    async fn call_ocr_api(&self, document_url: String) -> Result<MistralResponse> {
+    async fn call_ocr_api(&self, document_url: String, options: &OcrOptions) -> Result<MistralResponse> {
+        let (document_annotation_format, bbox_annotation_format) = annotation_formats(options);
         let request = OcrRequest {
-            model: "mistral-ocr-latest".to_string(),
+            model: self.model.clone(),
             document: DocumentUrl {
                 doc_type: "document_url".to_string(),
                 document_url,
             },
-            include_image_base64: true,
+            include_image_base64: self.include_images,
+            document_annotation_format,
+            bbox_annotation_format,
         };
         
-        let response = self.client.post("https://api.mistral.ai/v1/ocr")
+        let response = self.client.post(format!("{}/v1/ocr", self.base_url))
             .header("Authorization", format!("Bearer {}", self.api_key))
             .json(&request)
             .send()
             .await?;
         
         if !response.status().is_success() {
+            let status = response.status().as_u16();
             let error_text = response.text().await?;
-            return Err(anyhow::anyhow!("Mistral OCR failed: {}", error_text));
+            return Err(ProviderError::new(Some(status), format!("Mistral OCR failed: {}", error_text)).into());
         }
         
         Ok(response.json().await?)
     }
+
+    async fn get_batch_job(&self, job_id: &str) -> Result<BatchJobResponse> {
+        let response = self.client.get(format!("{}/v1/batch/jobs/{}", self.base_url, job_id))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let error_text = response.text().await?;
+            return Err(ProviderError::new(Some(status), format!("Mistral Batch status failed: {}", error_text)).into());
+        }
+
+        Ok(response.json().await?)
+    }
+
+    async fn download_file(&self, file_id: &str) -> Result<String> {
+        let response = self.client.get(format!("{}/v1/files/{}/content", self.base_url, file_id))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let error_text = response.text().await?;
+            return Err(ProviderError::new(Some(status), format!("Mistral Download failed: {}", error_text)).into());
+        }
+
+        Ok(response.text().await?)
+    }
+
+    // 上传文档和 JSONL 并创建任务；上传的文件依次加入 guards
+    async fn create_batch_job(&self, files: &[PathBuf], options: &OcrOptions, guards: &mut Vec<UploadGuard>) -> Result<String> {
+        // 1. 上传所有文档并生成 JSONL
+        let mut documents = Vec::new();
+        let mut jsonl = String::new();
+        for (index, path) in files.iter().enumerate() {
+            let file_res = self.upload_file(path).await?;
+            if !self.keep_uploaded_files {
+                guards.push(self.upload_guard(&file_res.id));
+            }
+            let document_url = self.get_signed_url(&file_res.id, BATCH_URL_EXPIRY_HOURS).await?;
+            documents.push(file_res.id);
+            let (document_annotation_format, bbox_annotation_format) = annotation_formats(options);
+
+            let line = BatchRequestLine {
+                custom_id: index.to_string(),
+                body: BatchOcrBody {
+                    document: DocumentUrl {
+                        doc_type: "document_url".to_string(),
+                        document_url,
+                    },
+                    include_image_base64: self.include_images,
+                    document_annotation_format,
+                    bbox_annotation_format,
+                },
+            };
+            jsonl.push_str(&serde_json::to_string(&line)?);
+            jsonl.push('\n');
+        }
+
+        // 2. 上传 JSONL 并创建任务
+        let input = self.upload_bytes("batch.jsonl".to_string(), jsonl.into_bytes(), "batch").await?;
+        guards.push(self.upload_guard(&input.id));
+        let request = BatchJobRequest {
+            input_files: vec![input.id],
+            endpoint: "/v1/ocr".to_string(),
+            model: self.model.clone(),
+            metadata: HashMap::from([(BATCH_DOCUMENTS_KEY.to_string(), documents.join(","))]),
+        };
+
+        let response = self.client.post(format!("{}/v1/batch/jobs", self.base_url))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .json(&request)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let error_text = response.text().await?;
+            return Err(ProviderError::new(Some(status), format!("Mistral Batch submit failed: {}", error_text)).into());
+        }
+
+        let job: BatchJobResponse = response.json().await?;
+        Ok(job.id)
+    }
+
+    // 任务结束后删除输入/输出文件，以及 (除非设置保留) 上传的文档
+    async fn cleanup_batch(&self, job: &BatchJobResponse) {
+        for id in batch_file_ids(job, !self.keep_uploaded_files) {
+            let _ = self.upload_guard(id).delete().await;
+        }
+    }
+}
+
+// 批量任务使用的文件：输入/输出文件，以及 (include_documents 时) 上传的文档
+fn batch_file_ids(job: &BatchJobResponse, include_documents: bool) -> Vec<&str> {
+    let mut file_ids: Vec<&str> = job.input_files.iter().map(|s| s.as_str()).collect();
+    file_ids.extend(job.output_file.as_deref());
+    file_ids.extend(job.error_file.as_deref());
+    if include_documents {
+        if let Some(documents) = job.metadata.get(BATCH_DOCUMENTS_KEY) {
+            file_ids.extend(documents.split(',').filter(|s| !s.is_empty()));
+        }
+    }
+    file_ids
+}
+
+fn annotation_formats(options: &OcrOptions) -> (Option<ResponseFormat>, Option<ResponseFormat>) {
+    let Some(schema) = &options.annotation_schema else { return (None, None) };
+    (
+        schema.document.as_ref().map(|s| ResponseFormat::new("document_annotation", s)),
+        schema.bbox.as_ref().map(|s| ResponseFormat::new("bbox_annotation", s)),
+    )
+}
+
+// 提取结果以 JSON 字符串返回；无法解析时保留原始文本
+fn parse_annotation(annotation: Option<String>) -> Option<serde_json::Value> {
+    annotation.map(|a| serde_json::from_str(&a).unwrap_or(serde_json::Value::String(a)))
+}
+
+// 将 Mistral 的响应转换为标准结构
+fn convert_response(mistral_res: MistralResponse) -> OcrResult {
+    let mut pages = Vec::new();
+    for p in mistral_res.pages {
+        let mut images = Vec::new();
+        for img in p.images {
+            let bbox = img.bbox();
+            if let Some(b64) = img.image_base64 {
+                // 保留 data URL 前缀 (data:image/jpeg;base64,...)，保存时据此确定格式
+                images.push(OcrImage {
+                    id: img.id,
+                    base64: b64,
+                    annotation: parse_annotation(img.image_annotation),
+                    bbox,
+                });
+            }
+        }
+        
+        pages.push(OcrPage {
+            number: p.index as usize,
+            markdown: p.markdown,
+            images,
+            dimensions: p.dimensions,
+        });
+    }
+    
+    OcrResult {
+        pages,
+        provider: None,
+        document_annotation: parse_annotation(mistral_res.document_annotation),
+    }
+}
+
+// 解析批量任务的输出/错误文件，按 custom_id 填入结果；
+// 无法解析的行只影响对应的请求，找不到 custom_id 的行保持 "Missing from batch output"
+fn parse_batch_results(content: &str, results: &mut [std::result::Result<OcrResult, String>]) {
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(line) else { continue };
+        let index = value.get("custom_id").and_then(|id| id.as_str()).and_then(|id| id.parse::<usize>().ok());
+        let Some(slot) = index.and_then(|i| results.get_mut(i)) else { continue };
+        *slot = match serde_json::from_value::<BatchResultLine>(value) {
+            Ok(line) => parse_batch_line(line),
+            Err(e) => Err(format!("Invalid batch output: {}", e)),
+        };
+    }
+}
+
+fn parse_batch_line(line: BatchResultLine) -> std::result::Result<OcrResult, String> {
+    match (line.response, line.error) {
+        (Some(response), _) if response.status_code < 300 => serde_json::from_value::<MistralResponse>(response.body)
+            .map(convert_response)
+            .map_err(|e| format!("Invalid OCR response: {}", e)),
+        (Some(response), _) => Err(format!("HTTP {}: {}", response.status_code, response.body)),
+        (None, error) => Err(error.map(|e| e.to_string()).unwrap_or_else(|| "No response".to_string())),
+    }
 }
 
 #[async_trait]
 impl OcrProvider for MistralProvider {
     fn id(&self) -> &str {
-        "mistral"
+        DESCRIPTOR.id
     }
 
     fn name(&self) -> &str {
-        "Mistral AI"
+        DESCRIPTOR.name
+    }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.model)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        DESCRIPTOR.capabilities
+    }
+
+    async fn list_uploads(&self) -> Result<Vec<String>> {
+        let files = self.list_ocr_files().await?;
+        Ok(files.into_iter().map(|f| format!("{} ({})", f.filename, f.id)).collect())
     }
 
-    async fn process_file(&self, file_path: &Path) -> Result<OcrResult> {
+    async fn purge_uploads(&self, pending_batches: &[(String, String)]) -> Result<PurgeReport> {
+        // 未完成的批量任务仍需要其输入文件和上传的文档；查询失败时不删除任何文件
+        let mut in_use = Vec::new();
+        for (_, job_id) in pending_batches.iter().filter(|(provider, _)| provider == DESCRIPTOR.id) {
+            let job = self.get_batch_job(job_id).await?;
+            in_use.extend(batch_file_ids(&job, true).into_iter().map(str::to_string));
+        }
+
+        let mut report = PurgeReport::default();
+        for file in self.list_ocr_files().await? {
+            if in_use.contains(&file.id) {
+                report.skipped += 1;
+                continue;
+            }
+            match self.upload_guard(&file.id).delete().await {
+                Ok(()) => report.deleted += 1,
+                Err(e) => report.failed.push(format!("{} ({}): {}", file.filename, file.id, e)),
+            }
+        }
+        Ok(report)
+    }
+
+    async fn process_file(&self, file_path: &Path, options: &OcrOptions) -> Result<OcrResult> {
         // 1. Upload
         let file_res = self.upload_file(file_path).await?;
+        let guard = (!self.keep_uploaded_files).then(|| self.upload_guard(&file_res.id));
         
-        // 2. Get URL
-        let url = self.get_signed_url(&file_res.id).await?;
-        
-        // 3. Process
-        let mistral_res = self.call_ocr_api(url).await?;
+        // 2. Get URL + 3. Process
+        let ocr_result = match self.get_signed_url(&file_res.id, SIGNED_URL_EXPIRY_HOURS).await {
+            Ok(url) => self.call_ocr_api(url, options).await,
+            Err(e) => Err(e),
+        };
+
+        // 无论成功与否都清理上传的文件，删除失败不影响识别结果
+        if let Some(guard) = guard {
+            let _ = guard.delete().await;
+        }
+        let mistral_res = ocr_result?;
         
         // 4. Convert to Standard Result
-        let mut pages = Vec::new();
-        for p in mistral_res.pages {
-            let mut images = Vec::new();
-            for img in p.images {
-                if let Some(b64) = img.image_base64 {
-                    // Normalize base64 string (remove data prefix if present)
-                    let clean_b64 = if b64.contains(",") {
-                        b64.split(',').nth(1).unwrap_or("").to_string()
-                    } else {
-                        b64
-                    };
-                    
-                    images.push(OcrImage {
-                        id: img.id,
-                        base64: clean_b64,
-                    });
+        Ok(convert_response(mistral_res))
+    }
+
+    async fn submit_batch(&self, files: &[PathBuf], options: &OcrOptions) -> Result<String> {
+        let mut guards = Vec::new();
+        match self.create_batch_job(files, options, &mut guards).await {
+            // 任务创建成功后文件由任务使用，结束时在 cleanup_batch 中删除
+            Ok(id) => {
+                guards.into_iter().for_each(UploadGuard::disarm);
+                Ok(id)
+            }
+            // 中途失败时删除已经上传的文件
+            Err(e) => {
+                for guard in guards {
+                    let _ = guard.delete().await;
                 }
+                Err(e)
             }
-            
-            pages.push(OcrPage {
-                number: p.index as usize,
-                markdown: p.markdown,
-                images,
-            });
         }
-        
-        Ok(OcrResult { pages })
+    }
+
+    async fn poll_batch(&self, job_id: &str) -> Result<BatchStatus> {
+        let job = self.get_batch_job(job_id).await?;
+        match job.status.as_str() {
+            "QUEUED" | "RUNNING" | "CANCELLATION_REQUESTED" => Ok(BatchStatus::Running {
+                completed: job.succeeded_requests + job.failed_requests,
+                total: job.total_requests,
+            }),
+            "SUCCESS" => {
+                let mut results: Vec<std::result::Result<OcrResult, String>> = (0..job.total_requests)
+                    .map(|_| Err("Missing from batch output".to_string()))
+                    .collect();
+                for file_id in job.output_file.iter().chain(job.error_file.iter()) {
+                    let content = self.download_file(file_id).await?;
+                    parse_batch_results(&content, &mut results);
+                }
+                // 结果保存前不删除输出文件，保存失败时可以重新下载
+                Ok(BatchStatus::Completed(results))
+            }
+            status => {
+                self.cleanup_batch(&job).await;
+                Ok(BatchStatus::Failed(format!("Batch job {}", status)))
+            }
+        }
+    }
+
+    async fn finish_batch(&self, job_id: &str) -> Result<()> {
+        let job = self.get_batch_job(job_id).await?;
+        self.cleanup_batch(&job).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed_batch_lines_only_fail_their_request() {
+        let content = [
+            r#"{"custom_id": "0", "response": {"status_code": 200, "body": {"pages": [{"index": 0, "markdown": "ok", "images": []}]}}}"#,
+            r#"{"custom_id": "1", "response": {"status_code": 200, "body": {"pages": "oops"}}}"#,
+            r#"{"custom_id": "2", "response": {"status_code": 422, "body": "bad"}}"#,
+            r#"not json"#,
+        ].join("\n");
+        let mut results: Vec<std::result::Result<OcrResult, String>> = (0..4).map(|_| Err("Missing".to_string())).collect();
+
+        parse_batch_results(&content, &mut results);
+
+        assert_eq!(results[0].as_ref().unwrap().pages[0].markdown, "ok");
+        assert!(results[1].as_ref().unwrap_err().starts_with("Invalid OCR response"));
+        assert!(results[2].as_ref().unwrap_err().starts_with("HTTP 422"));
+        assert_eq!(results[3].as_ref().unwrap_err(), "Missing");
     }
 }
@@ -0,0 +1,106 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use super::{OcrOptions, OcrProvider, OcrResult, OcrPage, OcrImage, ProviderError};
+use super::registry::Capabilities;
+
+// 失败模式
+#[derive(Debug, Clone)]
+pub enum MockFailure {
+    // 每次调用都失败，且不可重试
+    Always(String),
+    // 前 n 次调用以可重试的错误 (HTTP 503) 失败，之后正常返回
+    FirstCalls(usize, String),
+}
+
+// 测试用供应商：按调用顺序返回预设的结果，最后一个结果会被重复使用
+pub struct MockProvider {
+    id: &'static str,
+    results: Vec<OcrResult>,
+    failure: Option<MockFailure>,
+    calls: Arc<AtomicUsize>,
+}
+
+impl MockProvider {
+    pub fn new(results: Vec<OcrResult>) -> Self {
+        Self {
+            id: "mock",
+            results,
+            failure: None,
+            calls: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn with_failure(mut self, failure: MockFailure) -> Self {
+        self.failure = Some(failure);
+        self
+    }
+
+    // 组合供应商的测试中区分各成员
+    pub fn with_id(mut self, id: &'static str) -> Self {
+        self.id = id;
+        self
+    }
+
+    // 调用计数的共享句柄：供应商被装进 Box 或组合供应商后仍可读取
+    pub fn call_counter(&self) -> Arc<AtomicUsize> {
+        self.calls.clone()
+    }
+
+    // 生成一个简单的结果：每页一段文字，可选地带一张 1x1 的 PNG 图片
+    pub fn simple_result(page_count: usize, with_images: bool) -> OcrResult {
+        const PIXEL_PNG: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR4nGP4z8DwHwAFAAH/iZk9HQAAAABJRU5ErkJggg==";
+        let pages = (0..page_count).map(|i| {
+            let id = format!("img-{}.jpeg", i);
+            let (markdown, images) = if with_images {
+                (format!("Text of page {}\n\n![{}]({})", i, id, id), vec![OcrImage { id, base64: PIXEL_PNG.to_string(), annotation: None, bbox: None }])
+            } else {
+                (format!("Text of page {}", i), Vec::new())
+            };
+            OcrPage { number: i, markdown, images, dimensions: None }
+        }).collect();
+        OcrResult { pages, provider: None, document_annotation: None }
+    }
+}
+
+#[async_trait]
+impl OcrProvider for MockProvider {
+    fn id(&self) -> &str {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        "Mock"
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            accepts_pdf: true,
+            accepts_images: true,
+            returns_images: true,
+            requires_network: false,
+            stores_uploads: false,
+            supports_batch: false,
+            supports_annotations: true,
+        }
+    }
+
+    async fn process_file(&self, _file_path: &Path, _options: &OcrOptions) -> Result<OcrResult> {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst);
+
+        match &self.failure {
+            Some(MockFailure::Always(msg)) => return Err(anyhow::anyhow!("{}", msg)),
+            Some(MockFailure::FirstCalls(n, msg)) if call < *n => {
+                return Err(ProviderError::new(Some(503), msg.clone()).into());
+            }
+            _ => {}
+        }
+
+        self.results.get(call)
+            .or_else(|| self.results.last())
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("MockProvider has no results configured"))
+    }
+}
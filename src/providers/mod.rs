@@ -1,28 +1,185 @@
 use async_trait::async_trait;
 use anyhow::Result;
-use std::path::Path;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use registry::Capabilities;
 
+pub mod fallback;
 pub mod mistral;
+pub mod openai_compat;
+pub mod registry;
+pub mod replay;
+pub mod tesseract;
+#[cfg(test)]
+pub mod mock;
 
 // 统一的图像结构
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OcrImage {
     pub id: String,
+    // base64 或 data URL
     pub base64: String,
+    // 按 bbox 结构化提取 Schema 得到的数据
+    #[serde(default)]
+    pub annotation: Option<serde_json::Value>,
+    // 图片在页面中的位置 (像素，坐标系见 OcrPage.dimensions)
+    #[serde(default)]
+    pub bbox: Option<BoundingBox>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub top_left_x: u32,
+    pub top_left_y: u32,
+    pub bottom_right_x: u32,
+    pub bottom_right_y: u32,
+}
+
+// 供应商处理页面时使用的尺寸 (像素) 和分辨率
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PageDimensions {
+    pub dpi: u32,
+    pub height: u32,
+    pub width: u32,
+}
+
+impl BoundingBox {
+    // 在页面宽度中所占的比例 (0.0 - 1.0)
+    pub fn width_ratio(&self, page: &PageDimensions) -> f32 {
+        if page.width == 0 {
+            return 1.0;
+        }
+        (self.bottom_right_x.saturating_sub(self.top_left_x) as f32 / page.width as f32).clamp(0.0, 1.0)
+    }
 }
 
 // 统一的页面结构
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OcrPage {
+    // 供应商返回的页面索引 (从 0 开始，相对于提交的文件或分块)
     pub number: usize,
     pub markdown: String,
     pub images: Vec<OcrImage>,
+    #[serde(default)]
+    pub dimensions: Option<PageDimensions>,
+}
+
+impl OcrPage {
+    // 文档中的页码 (从 1 开始)：标题、图片文件名和 result.json 都以此为准
+    pub fn global_number(&self, page_offset: u32) -> u32 {
+        page_offset + self.number as u32 + 1
+    }
 }
 
 // 统一的结果结构
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OcrResult {
     pub pages: Vec<OcrPage>,
+    // 实际产生结果的供应商 ID，由组合供应商 (如 fallback) 填写
+    #[serde(default)]
+    pub provider: Option<String>,
+    // 按文档结构化提取 Schema 得到的数据
+    #[serde(default)]
+    pub document_annotation: Option<serde_json::Value>,
+}
+
+// 结构化提取使用的 JSON Schema：document 作用于整个文档，bbox 作用于每个图片/图表区域
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnnotationSchema {
+    pub document: Option<serde_json::Value>,
+    pub bbox: Option<serde_json::Value>,
+}
+
+impl AnnotationSchema {
+    // 文件内容可以是单个 JSON Schema (用于文档级提取)，
+    // 也可以是 {"document": {...}, "bbox": {...}} 形式
+    pub fn load(path: &Path) -> Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        let is_split = value.get("type").is_none()
+            && (value.get("document").is_some() || value.get("bbox").is_some());
+        if is_split {
+            Ok(serde_json::from_value(value)?)
+        } else {
+            Ok(Self { document: Some(value), bbox: None })
+        }
+    }
+}
+
+// 随任务传给供应商的选项
+#[derive(Debug, Clone, Default)]
+pub struct OcrOptions {
+    // 仅 supports_annotations 的供应商使用
+    pub annotation_schema: Option<AnnotationSchema>,
+}
+
+// 供应商请求失败：status 为 HTTP 状态码，None 表示服务不可达
+#[derive(Debug)]
+pub struct ProviderError {
+    pub status: Option<u16>,
+    pub message: String,
+    // 本地引擎的失败 (无法启动、返回错误)，重试同样会失败
+    pub local: bool,
+}
+
+impl ProviderError {
+    pub fn new(status: Option<u16>, message: String) -> Self {
+        Self { status, message, local: false }
+    }
+
+    pub fn local(message: String) -> Self {
+        Self { status: None, message, local: true }
+    }
+
+    // 超时、限流、服务端错误或服务不可达时值得换一个供应商重试
+    pub fn is_retryable(&self) -> bool {
+        if self.local {
+            return false;
+        }
+        match self.status {
+            None => true,
+            Some(code) => code == 408 || code == 429 || code >= 500,
+        }
+    }
+}
+
+impl std::fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.status {
+            Some(code) => write!(f, "{} (HTTP {})", self.message, code),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+pub fn is_retryable(err: &anyhow::Error) -> bool {
+    if let Some(e) = err.downcast_ref::<ProviderError>() {
+        return e.is_retryable();
+    }
+    if let Some(e) = err.downcast_ref::<reqwest::Error>() {
+        return e.is_timeout() || e.is_connect();
+    }
+    false
+}
+
+// 清理上传文件的结果：每个文件都会尝试删除，失败的记为 "文件名 (ID): 错误"
+#[derive(Debug, Default)]
+pub struct PurgeReport {
+    pub deleted: usize,
+    // 仍被未完成的批量任务使用而保留的文件数
+    pub skipped: usize,
+    pub failed: Vec<String>,
+}
+
+// 批量任务的状态
+#[derive(Debug)]
+pub enum BatchStatus {
+    // 排队或运行中：(已完成的请求数, 请求总数)
+    Running { completed: usize, total: usize },
+    // 每个请求的结果，与提交的文件顺序一致
+    Completed(Vec<std::result::Result<OcrResult, String>>),
+    Failed(String),
 }
 
 // 核心接口：所有 OCR 供应商都必须实现这个 Trait
@@ -34,6 +191,40 @@ pub trait OcrProvider: Send + Sync {
     // 获取显示名称 (如 "Mistral AI")
     fn name(&self) -> &str;
 
+    // 使用的模型名称，没有模型概念的供应商返回 None
+    fn model(&self) -> Option<&str> {
+        None
+    }
+
+    // 支持的输入输出能力
+    fn capabilities(&self) -> Capabilities;
+
     // 处理单个文件，返回标准化的结果
-    async fn process_file(&self, file_path: &Path) -> Result<OcrResult>;
+    async fn process_file(&self, file_path: &Path, options: &OcrOptions) -> Result<OcrResult>;
+
+    // 列出之前上传到供应商存储中的文件 (仅 stores_uploads 的供应商支持)
+    async fn list_uploads(&self) -> Result<Vec<String>> {
+        Err(anyhow::anyhow!("{} does not store uploaded files", self.name()))
+    }
+
+    // 删除所有上传过的文件，单个文件删除失败不影响其他文件；
+    // pending_batches 为未完成的批量任务 (供应商 ID, 任务 ID)，其使用的文件不会被删除
+    async fn purge_uploads(&self, _pending_batches: &[(String, String)]) -> Result<PurgeReport> {
+        Err(anyhow::anyhow!("{} does not store uploaded files", self.name()))
+    }
+
+    // 提交批量任务 (仅 supports_batch 的供应商支持)，每个文件一个请求，返回任务 ID
+    async fn submit_batch(&self, _files: &[PathBuf], _options: &OcrOptions) -> Result<String> {
+        Err(anyhow::anyhow!("{} does not support batch jobs", self.name()))
+    }
+
+    // 查询批量任务；完成时下载结果，供应商端的文件保留到 finish_batch
+    async fn poll_batch(&self, _job_id: &str) -> Result<BatchStatus> {
+        Err(anyhow::anyhow!("{} does not support batch jobs", self.name()))
+    }
+
+    // 结果已保存到本地后调用，清理供应商端的输入、输出文件
+    async fn finish_batch(&self, _job_id: &str) -> Result<()> {
+        Ok(())
+    }
 }
@@ -0,0 +1,286 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use super::{OcrOptions, OcrProvider, OcrResult, OcrPage, ProviderError};
+use super::registry::{Capabilities, ConfigField, FieldKind, ProviderDescriptor, ProviderSettings};
+use crate::pdf_utils;
+
+const DEFAULT_PROMPT: &str = "Transcribe all text in this page image to Markdown. \
+Keep the reading order, headings, lists and tables (as Markdown tables). \
+Output only the Markdown, without any explanation or code fences.";
+
+// 发送前将图片长边缩放到此尺寸以内，避免请求过大
+const MAX_IMAGE_SIDE: u32 = 2048;
+
+// --- 注册信息 ---
+
+pub static DESCRIPTOR: ProviderDescriptor = ProviderDescriptor {
+    id: "openai_compat",
+    name: "OpenAI-compatible Vision",
+    fields: &[
+        ConfigField { key: "base_url", label: "field_base_url", kind: FieldKind::Url, default: "https://api.openai.com/v1", required: true },
+        ConfigField { key: "api_key", label: "field_api_key", kind: FieldKind::Secret, default: "", required: false },
+        ConfigField { key: "model", label: "field_model", kind: FieldKind::Text, default: "gpt-4o-mini", required: true },
+        ConfigField { key: "prompt", label: "field_prompt", kind: FieldKind::Multiline, default: DEFAULT_PROMPT, required: false },
+    ],
+    capabilities: Capabilities {
+        // 仅支持扫描件等每页为图片的 PDF
+        accepts_pdf: true,
+        accepts_images: true,
+        returns_images: false,
+        requires_network: true,
+        stores_uploads: false,
+        supports_batch: false,
+        supports_annotations: false,
+    },
+    help_url: None,
+    factory: build,
+};
+
+fn build(settings: &ProviderSettings) -> Result<Box<dyn OcrProvider>> {
+    Ok(Box::new(OpenAiCompatProvider::new(
+        settings.require("base_url")?,
+        settings.get("api_key"),
+        settings.require("model")?,
+        settings.get("prompt"),
+    )))
+}
+
+// --- Chat Completions API 数据结构 ---
+
+#[derive(Debug, Serialize)]
+struct ChatRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+    temperature: f32,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatMessage {
+    role: String,
+    content: Vec<ContentPart>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+}
+
+#[derive(Debug, Serialize)]
+struct ImageUrl {
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    message: ChatResponseMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponseMessage {
+    content: Option<String>,
+}
+
+// --- Provider 实现 ---
+
+pub struct OpenAiCompatProvider {
+    client: Client,
+    base_url: String,
+    api_key: String,
+    model: String,
+    prompt: String,
+}
+
+impl OpenAiCompatProvider {
+    pub fn new(base_url: String, api_key: String, model: String, prompt: String) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            model,
+            prompt,
+        }
+    }
+
+    async fn recognize_page(&self, image: &::image::DynamicImage) -> Result<String> {
+        let request = ChatRequest {
+            model: self.model.clone(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: vec![
+                    ContentPart::Text { text: self.prompt.clone() },
+                    ContentPart::ImageUrl { image_url: ImageUrl { url: encode_data_url(image)? } },
+                ],
+            }],
+            temperature: 0.0,
+        };
+
+        let mut builder = self.client.post(format!("{}/chat/completions", self.base_url)).json(&request);
+        // 本地部署的服务 (vLLM/Ollama) 通常不需要 Key
+        if !self.api_key.is_empty() {
+            builder = builder.header("Authorization", format!("Bearer {}", self.api_key));
+        }
+        let response = builder.send().await?;
+
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let error_text = response.text().await?;
+            return Err(ProviderError::new(Some(status), format!("Chat completion failed: {}", error_text)).into());
+        }
+
+        let res: ChatResponse = response.json().await?;
+        let content = res.choices.into_iter()
+            .next()
+            .and_then(|c| c.message.content)
+            .ok_or_else(|| anyhow::anyhow!("Chat completion returned no content"))?;
+        Ok(strip_code_fence(&content))
+    }
+}
+
+#[async_trait]
+impl OcrProvider for OpenAiCompatProvider {
+    fn id(&self) -> &str {
+        DESCRIPTOR.id
+    }
+
+    fn name(&self) -> &str {
+        DESCRIPTOR.name
+    }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.model)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        DESCRIPTOR.capabilities
+    }
+
+    async fn process_file(&self, file_path: &Path, _options: &OcrOptions) -> Result<OcrResult> {
+        let path = file_path.to_path_buf();
+        let images = tokio::task::spawn_blocking(move || pdf_utils::load_page_images(path)).await??;
+
+        let mut pages = Vec::new();
+        for (index, image) in images.iter().enumerate() {
+            let markdown = self.recognize_page(image).await?;
+            pages.push(OcrPage {
+                number: index,
+                markdown,
+                images: Vec::new(),
+                dimensions: None,
+            });
+        }
+
+        Ok(OcrResult { pages, provider: None, document_annotation: None })
+    }
+}
+
+fn encode_data_url(image: &::image::DynamicImage) -> Result<String> {
+    let image = if image.width() > MAX_IMAGE_SIDE || image.height() > MAX_IMAGE_SIDE {
+        image.resize(MAX_IMAGE_SIDE, MAX_IMAGE_SIDE, ::image::imageops::FilterType::Triangle)
+    } else {
+        image.clone()
+    };
+
+    let mut bytes = Vec::new();
+    image.write_to(&mut std::io::Cursor::new(&mut bytes), ::image::ImageOutputFormat::Png)?;
+    Ok(format!("data:image/png;base64,{}", general_purpose::STANDARD.encode(bytes)))
+}
+
+// 模型有时会把结果包在 ```markdown 代码块中
+fn strip_code_fence(content: &str) -> String {
+    let trimmed = content.trim();
+    if let Some(rest) = trimmed.strip_prefix("```") {
+        let body = rest.split_once('\n').map(|(_, b)| b).unwrap_or("");
+        return body.trim_end().trim_end_matches("```").trim().to_string();
+    }
+    trimmed.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // 本地桩服务：依次用 responses 应答每个连接，返回收到的请求 (路径, JSON 请求体)
+    async fn stub_server(responses: Vec<(u16, String)>) -> (String, tokio::task::JoinHandle<Vec<(String, serde_json::Value)>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut data = Vec::new();
+                let mut buf = [0u8; 8192];
+                let header_end = loop {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    data.extend_from_slice(&buf[..n]);
+                    if let Some(pos) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break pos + 4;
+                    }
+                };
+                let head = String::from_utf8_lossy(&data[..header_end]).to_string();
+                let length: usize = head.lines()
+                    .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse().unwrap()))
+                    .unwrap_or(0);
+                while data.len() < header_end + length {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    data.extend_from_slice(&buf[..n]);
+                }
+                let path = head.split_whitespace().nth(1).unwrap_or_default().to_string();
+                requests.push((path, serde_json::from_slice(&data[header_end..]).unwrap()));
+
+                let response = format!(
+                    "HTTP/1.1 {} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status, body.len(), body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+        (base_url, handle)
+    }
+
+    fn write_image(dir: &Path) -> std::path::PathBuf {
+        let path = dir.join("scan.png");
+        ::image::RgbImage::from_pixel(4, 4, ::image::Rgb([255, 255, 255])).save(&path).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn requests_and_responses_follow_chat_completions() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = write_image(dir.path());
+        let reply = serde_json::json!({"choices": [{"message": {"content": "```markdown\n# Title\n\ntext\n```"}}]}).to_string();
+        let (base_url, server) = stub_server(vec![(200, reply), (429, "{\"error\": \"slow down\"}".to_string())]).await;
+        let provider = OpenAiCompatProvider::new(base_url, String::new(), "local-vlm".into(), "Read it".into());
+
+        let result = provider.process_file(&input, &OcrOptions::default()).await.unwrap();
+        assert_eq!(result.pages.len(), 1);
+        assert_eq!(result.pages[0].number, 0);
+        assert_eq!(result.pages[0].markdown, "# Title\n\ntext");
+
+        let error = provider.process_file(&input, &OcrOptions::default()).await.unwrap_err();
+        let error = error.downcast_ref::<ProviderError>().unwrap();
+        assert_eq!(error.status, Some(429));
+        assert!(error.is_retryable());
+
+        let requests = server.await.unwrap();
+        let (path, body) = &requests[0];
+        assert_eq!(path, "/v1/chat/completions");
+        assert_eq!(body["model"], "local-vlm");
+        let content = &body["messages"][0]["content"];
+        assert_eq!(content[0], serde_json::json!({"type": "text", "text": "Read it"}));
+        assert_eq!(content[1]["type"], "image_url");
+        assert!(content[1]["image_url"]["url"].as_str().unwrap().starts_with("data:image/png;base64,"));
+    }
+}
@@ -0,0 +1,160 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use super::{OcrProvider, fallback, mistral, openai_compat, tesseract};
+
+// 所有已注册的供应商。新增供应商只需实现模块并在此处加入其描述符
+static PROVIDERS: &[&ProviderDescriptor] = &[
+    &mistral::DESCRIPTOR,
+    &openai_compat::DESCRIPTOR,
+    &tesseract::DESCRIPTOR,
+    &fallback::DESCRIPTOR,
+];
+
+// 每个供应商保存的配置：字段 key -> 值
+pub type SettingsMap = HashMap<String, HashMap<String, String>>;
+
+pub type ProviderFactory = fn(&ProviderSettings) -> Result<Box<dyn OcrProvider>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Text,
+    Secret,
+    Url,
+    Bool,
+    Multiline,
+}
+
+// 设置表单中的一个字段，GUI 根据它自动生成输入控件
+#[derive(Debug, Clone, Copy)]
+pub struct ConfigField {
+    pub key: &'static str,
+    // i18n key，缺少翻译时直接显示
+    pub label: &'static str,
+    pub kind: FieldKind,
+    pub default: &'static str,
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Capabilities {
+    pub accepts_pdf: bool,
+    pub accepts_images: bool,
+    // 是否在结果中返回嵌入的图片
+    pub returns_images: bool,
+    pub requires_network: bool,
+    // 是否会把文档上传到供应商的存储中 (支持列出/清理)
+    pub stores_uploads: bool,
+    // 是否支持异步批量任务 (提交后稍后取回结果)
+    pub supports_batch: bool,
+    // 是否支持按 JSON Schema 提取结构化数据
+    pub supports_annotations: bool,
+}
+
+pub struct ProviderDescriptor {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub fields: &'static [ConfigField],
+    pub capabilities: Capabilities,
+    // 申请 API Key 等的帮助链接
+    pub help_url: Option<&'static str>,
+    pub factory: ProviderFactory,
+}
+
+impl ProviderDescriptor {
+    // 返回必填但尚未配置的字段
+    pub fn missing_fields(&self, settings: &SettingsMap) -> Vec<&'static ConfigField> {
+        let values = settings.get(self.id);
+        self.fields.iter()
+            .filter(|f| f.required)
+            .filter(|f| values.and_then(|v| v.get(f.key)).map(|v| v.trim().is_empty()).unwrap_or(true))
+            .collect()
+    }
+}
+
+// 构造供应商时传入的配置视图，未设置的字段回退到默认值
+pub struct ProviderSettings<'a> {
+    descriptor: &'static ProviderDescriptor,
+    values: Option<&'a HashMap<String, String>>,
+    // 全部供应商的配置，供组合供应商构造其成员
+    all: &'a SettingsMap,
+}
+
+impl ProviderSettings<'_> {
+    pub fn get(&self, key: &str) -> String {
+        let value = self.values.and_then(|v| v.get(key)).map(|v| v.trim().to_string());
+        match value {
+            Some(v) if !v.is_empty() => v,
+            _ => self.descriptor.fields.iter()
+                .find(|f| f.key == key)
+                .map(|f| f.default.to_string())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn require(&self, key: &str) -> Result<String> {
+        let value = self.get(key);
+        if value.is_empty() {
+            return Err(anyhow::anyhow!("{}: missing setting '{}'", self.descriptor.name, key));
+        }
+        Ok(value)
+    }
+
+    pub fn get_bool(&self, key: &str) -> bool {
+        self.get(key) == "true"
+    }
+
+    pub fn build_provider(&self, id: &str) -> Result<Box<dyn OcrProvider>> {
+        build(id, self.all)
+    }
+}
+
+pub fn all() -> &'static [&'static ProviderDescriptor] {
+    PROVIDERS
+}
+
+pub fn find(id: &str) -> Option<&'static ProviderDescriptor> {
+    PROVIDERS.iter().copied().find(|d| d.id == id)
+}
+
+pub fn build(id: &str, settings: &SettingsMap) -> Result<Box<dyn OcrProvider>> {
+    let descriptor = find(id).ok_or_else(|| anyhow::anyhow!("Unknown provider: {}", id))?;
+    let provider_settings = ProviderSettings {
+        descriptor,
+        values: settings.get(id),
+        all: settings,
+    };
+    (descriptor.factory)(&provider_settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_provider_builds_from_its_defaults() {
+        // 只填写默认值为空的必填字段 (如 API Key)，其他字段使用默认值
+        let mut settings = SettingsMap::new();
+        for descriptor in all() {
+            let values = descriptor.fields.iter()
+                .filter(|f| f.required && f.default.is_empty())
+                .map(|f| (f.key.to_string(), "test".to_string()))
+                .collect();
+            settings.insert(descriptor.id.to_string(), values);
+        }
+
+        for descriptor in all() {
+            match build(descriptor.id, &settings) {
+                Ok(provider) => assert_eq!(provider.id(), descriptor.id),
+                Err(e) => panic!("{}: {}", descriptor.id, e),
+            }
+            // 完全未配置时只会因缺少必填字段而失败
+            if let Err(e) = build(descriptor.id, &SettingsMap::new()) {
+                assert!(e.to_string().contains("missing setting"), "{}: {}", descriptor.id, e);
+            }
+        }
+
+        assert!(find("unknown").is_none());
+        let Err(e) = build("unknown", &settings) else { panic!("unknown provider was built") };
+        assert_eq!(e.to_string(), "Unknown provider: unknown");
+    }
+}
@@ -0,0 +1,146 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use super::{OcrOptions, OcrProvider, OcrResult};
+use super::registry::Capabilities;
+use crate::pdf_utils;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayMode {
+    // 调用真实供应商，并把响应保存为 fixture
+    Record,
+    // 只读取 fixture，不发出任何请求
+    Replay,
+}
+
+// 录制/回放包装器：fixture 以输入文件内容、供应商、模型和选项共同的 SHA-256 为键，
+// 换了模型或 Schema 时不会读到旧的响应
+pub struct ReplayProvider {
+    inner: Option<Box<dyn OcrProvider>>,
+    // 回放时使用录制时的供应商 ID 和模型
+    provider_id: String,
+    model: Option<String>,
+    fixture_dir: PathBuf,
+    mode: ReplayMode,
+}
+
+impl ReplayProvider {
+    pub fn record(inner: Box<dyn OcrProvider>, fixture_dir: PathBuf) -> Self {
+        Self {
+            provider_id: inner.id().to_string(),
+            model: inner.model().map(str::to_string),
+            inner: Some(inner),
+            fixture_dir,
+            mode: ReplayMode::Record,
+        }
+    }
+
+    pub fn replay(fixture_dir: PathBuf, provider_id: String, model: Option<String>) -> Self {
+        Self {
+            inner: None,
+            provider_id,
+            model,
+            fixture_dir,
+            mode: ReplayMode::Replay,
+        }
+    }
+
+    pub fn fixture_path(&self, file_path: &Path, options: &OcrOptions) -> Result<PathBuf> {
+        let mut hasher = Sha256::new();
+        hasher.update(std::fs::read(file_path)?);
+        for part in [self.provider_id.as_str(), self.model.as_deref().unwrap_or_default()] {
+            hasher.update([0]);
+            hasher.update(part);
+        }
+        hasher.update([0]);
+        hasher.update(serde_json::to_vec(&options.annotation_schema)?);
+        Ok(self.fixture_dir.join(format!("{}_{:x}.json", self.provider_id, hasher.finalize())))
+    }
+}
+
+#[async_trait]
+impl OcrProvider for ReplayProvider {
+    fn id(&self) -> &str {
+        &self.provider_id
+    }
+
+    fn name(&self) -> &str {
+        self.inner.as_ref().map(|p| p.name()).unwrap_or("Replay")
+    }
+
+    fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    // 图片总是原样传入，使 fixture 以原图内容为键
+    // (转换得到的 PDF 含有时间戳，每次内容都不同)
+    fn capabilities(&self) -> Capabilities {
+        let inner = self.inner.as_ref().map(|p| p.capabilities()).unwrap_or_default();
+        Capabilities {
+            accepts_pdf: true,
+            accepts_images: true,
+            returns_images: inner.returns_images || self.inner.is_none(),
+            requires_network: inner.requires_network,
+            // 上传文件的维护操作请直接在对应供应商的设置中进行
+            stores_uploads: false,
+            supports_batch: false,
+            supports_annotations: inner.supports_annotations || self.inner.is_none(),
+        }
+    }
+
+    async fn process_file(&self, file_path: &Path, options: &OcrOptions) -> Result<OcrResult> {
+        let fixture = self.fixture_path(file_path, options)?;
+        match (self.mode, &self.inner) {
+            (ReplayMode::Record, Some(inner)) => {
+                let result = if pdf_utils::is_image_file(file_path) && !inner.capabilities().accepts_images {
+                    let temp_dir = tempfile::tempdir()?;
+                    let pdf_path = temp_dir.path().join("converted.pdf");
+                    pdf_utils::convert_image_to_pdf(file_path, &pdf_path)?;
+                    inner.process_file(&pdf_path, options).await?
+                } else {
+                    inner.process_file(file_path, options).await?
+                };
+                std::fs::create_dir_all(&self.fixture_dir)?;
+                std::fs::write(&fixture, serde_json::to_string_pretty(&result)?)?;
+                Ok(result)
+            }
+            _ => {
+                let content = std::fs::read_to_string(&fixture)
+                    .map_err(|e| anyhow::anyhow!("No fixture for {} ({}): {}", file_path.display(), fixture.display(), e))?;
+                Ok(serde_json::from_str(&content)?)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::AnnotationSchema;
+    use crate::providers::mock::MockProvider;
+
+    #[tokio::test]
+    async fn recorded_responses_are_replayed_for_the_same_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("scan.png");
+        ::image::RgbImage::from_pixel(4, 4, ::image::Rgb([255, 255, 255])).save(&input).unwrap();
+        let fixtures = dir.path().join("fixtures");
+
+        let mock = MockProvider::new(vec![MockProvider::simple_result(2, true)]);
+        let recorded = ReplayProvider::record(Box::new(mock), fixtures.clone())
+            .process_file(&input, &OcrOptions::default()).await.unwrap();
+
+        let replay = ReplayProvider::replay(fixtures.clone(), "mock".into(), None);
+        let replayed = replay.process_file(&input, &OcrOptions::default()).await.unwrap();
+        assert_eq!(serde_json::to_value(&replayed).unwrap(), serde_json::to_value(&recorded).unwrap());
+
+        // 换了 Schema、模型或供应商都不会命中这个 fixture
+        let schema = OcrOptions { annotation_schema: Some(AnnotationSchema { document: Some(serde_json::json!({"type": "object"})), bbox: None }) };
+        assert!(replay.process_file(&input, &schema).await.is_err());
+        let other_model = ReplayProvider::replay(fixtures.clone(), "mock".into(), Some("v2".into()));
+        assert!(other_model.process_file(&input, &OcrOptions::default()).await.is_err());
+        let other_provider = ReplayProvider::replay(fixtures, "mistral".into(), None);
+        assert!(other_provider.process_file(&input, &OcrOptions::default()).await.is_err());
+    }
+}
@@ -0,0 +1,230 @@
+use async_trait::async_trait;
+use anyhow::Result;
+use std::path::Path;
+use tokio::process::Command;
+use super::{OcrOptions, OcrProvider, OcrResult, OcrPage, ProviderError};
+use super::registry::{Capabilities, ConfigField, FieldKind, ProviderDescriptor, ProviderSettings};
+use crate::pdf_utils;
+
+// --- 注册信息 ---
+
+pub static DESCRIPTOR: ProviderDescriptor = ProviderDescriptor {
+    id: "tesseract",
+    name: "Tesseract (Offline)",
+    fields: &[
+        ConfigField { key: "binary", label: "field_tesseract_binary", kind: FieldKind::Text, default: "tesseract", required: true },
+        // 多个语言用 + 连接，如 eng+chi_sim
+        ConfigField { key: "languages", label: "field_languages", kind: FieldKind::Text, default: "eng", required: true },
+        ConfigField { key: "psm", label: "field_psm", kind: FieldKind::Text, default: "3", required: false },
+    ],
+    capabilities: Capabilities {
+        // 仅支持扫描件等每页为图片的 PDF
+        accepts_pdf: true,
+        accepts_images: true,
+        // 只输出文本，不会返回嵌入的图片
+        returns_images: false,
+        requires_network: false,
+        stores_uploads: false,
+        supports_batch: false,
+        supports_annotations: false,
+    },
+    help_url: Some("https://tesseract-ocr.github.io/tessdoc/Installation.html"),
+    factory: build,
+};
+
+fn build(settings: &ProviderSettings) -> Result<Box<dyn OcrProvider>> {
+    Ok(Box::new(TesseractProvider::new(
+        settings.require("binary")?,
+        settings.require("languages")?,
+        settings.get("psm"),
+    )))
+}
+
+// --- Provider 实现 ---
+
+// 调用本地 tesseract 命令行，文档不会离开本机
+pub struct TesseractProvider {
+    binary: String,
+    languages: String,
+    psm: String,
+}
+
+impl TesseractProvider {
+    pub fn new(binary: String, languages: String, psm: String) -> Self {
+        Self { binary, languages, psm }
+    }
+
+    async fn recognize_image(&self, image_path: &Path) -> Result<String> {
+        let mut command = Command::new(&self.binary);
+        command.arg(image_path).arg("stdout").arg("-l").arg(&self.languages);
+        if !self.psm.is_empty() {
+            command.arg("--psm").arg(&self.psm);
+        }
+
+        let output = command.output().await
+            .map_err(|e| ProviderError::local(format!("Failed to run {}: {}", self.binary, e)))?;
+        if !output.status.success() {
+            let message = format!("Tesseract failed ({}): {}", output.status, String::from_utf8_lossy(&output.stderr).trim());
+            return Err(ProviderError::local(message).into());
+        }
+
+        Ok(text_to_markdown(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
+
+#[async_trait]
+impl OcrProvider for TesseractProvider {
+    fn id(&self) -> &str {
+        DESCRIPTOR.id
+    }
+
+    fn name(&self) -> &str {
+        DESCRIPTOR.name
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        DESCRIPTOR.capabilities
+    }
+
+    async fn process_file(&self, file_path: &Path, _options: &OcrOptions) -> Result<OcrResult> {
+        // tesseract 可以直接读取图片文件
+        if pdf_utils::is_image_file(file_path) {
+            let markdown = self.recognize_image(file_path).await?;
+            return Ok(OcrResult {
+                pages: vec![OcrPage { number: 0, markdown, images: Vec::new(), dimensions: None }],
+                provider: None,
+                document_annotation: None,
+            });
+        }
+
+        let path = file_path.to_path_buf();
+        let images = tokio::task::spawn_blocking(move || pdf_utils::load_page_images(path)).await??;
+        let temp_dir = tempfile::tempdir()?;
+
+        let mut pages = Vec::new();
+        for (index, image) in images.iter().enumerate() {
+            let image_path = temp_dir.path().join(format!("page_{}.png", index));
+            image.save(&image_path)?;
+            let markdown = self.recognize_image(&image_path).await?;
+            pages.push(OcrPage {
+                number: index,
+                markdown,
+                images: Vec::new(),
+                dimensions: None,
+            });
+        }
+
+        Ok(OcrResult { pages, provider: None, document_annotation: None })
+    }
+}
+
+// Tesseract 输出纯文本：去掉行尾空白和多余空行，段落之间保留一个空行
+fn text_to_markdown(text: &str) -> String {
+    let mut paragraphs = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    for line in text.lines().map(|l| l.trim_end()) {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(current.join("\n"));
+                current.clear();
+            }
+        } else {
+            current.push(line);
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current.join("\n"));
+    }
+    paragraphs.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use printpdf::{ColorBits, ColorSpace, Image, ImageTransform, ImageXObject, Mm, PdfDocument, Px};
+
+    // 每页一张 4x4 灰色图片的 PDF
+    fn write_scanned_pdf(path: &Path, pages: usize) {
+        let (doc, page, layer) = PdfDocument::new("scan", Mm(10.0), Mm(10.0), "Layer 1");
+        let mut layers = vec![doc.get_page(page).get_layer(layer)];
+        for _ in 1..pages {
+            let (page, layer) = doc.add_page(Mm(10.0), Mm(10.0), "Layer 1");
+            layers.push(doc.get_page(page).get_layer(layer));
+        }
+        for layer in layers {
+            Image::from(ImageXObject {
+                width: Px(4),
+                height: Px(4),
+                color_space: ColorSpace::Rgb,
+                bits_per_component: ColorBits::Bit8,
+                interpolate: false,
+                image_data: vec![128; 4 * 4 * 3],
+                clipping_bbox: None,
+                image_filter: None,
+                smask: None,
+            }).add_to_layer(layer, ImageTransform::default());
+        }
+        doc.save(&mut std::io::BufWriter::new(std::fs::File::create(path).unwrap())).unwrap();
+    }
+
+    #[test]
+    fn text_is_split_into_paragraphs() {
+        let text = "First line   \nsecond line\n\n\n  \nNext paragraph\n\n";
+        assert_eq!(text_to_markdown(text), "First line\nsecond line\n\nNext paragraph");
+        assert_eq!(text_to_markdown("\n \n"), "");
+    }
+
+    #[tokio::test]
+    async fn missing_binary_is_reported_as_provider_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join("scan.png");
+        ::image::RgbImage::from_pixel(4, 4, ::image::Rgb([255, 255, 255])).save(&image).unwrap();
+        let provider = TesseractProvider::new(dir.path().join("no-tesseract").display().to_string(), "eng".into(), String::new());
+
+        let err = provider.process_file(&image, &OcrOptions::default()).await.unwrap_err();
+        let err = err.downcast_ref::<ProviderError>().unwrap();
+        assert!(err.message.starts_with("Failed to run"));
+        assert!(!err.is_retryable());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn failing_binary_is_reported_as_provider_error() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("tesseract");
+        std::fs::write(&binary, "#!/bin/sh\necho 'Error opening data file' >&2\nexit 1\n").unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let image = dir.path().join("scan.png");
+        ::image::RgbImage::from_pixel(4, 4, ::image::Rgb([255, 255, 255])).save(&image).unwrap();
+        let provider = TesseractProvider::new(binary.display().to_string(), "eng".into(), String::new());
+
+        let err = provider.process_file(&image, &OcrOptions::default()).await.unwrap_err();
+        let err = err.downcast_ref::<ProviderError>().unwrap();
+        assert!(err.message.ends_with("Error opening data file"));
+        assert!(!err.is_retryable());
+    }
+
+    // 用一个打印图片文件名的脚本代替 tesseract
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn pdf_pages_are_numbered_within_the_chunk() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("tesseract");
+        std::fs::write(&binary, "#!/bin/sh\necho \"text of $(basename \"$1\")\"\n").unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let input = dir.path().join("chunk.pdf");
+        write_scanned_pdf(&input, 2);
+        let provider = TesseractProvider::new(binary.display().to_string(), "eng".into(), String::new());
+
+        let result = provider.process_file(&input, &OcrOptions::default()).await.unwrap();
+
+        // OcrPage.number 从 0 开始且相对于分块，分块之前有 10 页时对应文档的第 11、12 页
+        assert_eq!(result.pages.iter().map(|p| p.number).collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(result.pages[1].markdown, "text of page_1.png");
+        assert_eq!(result.pages.iter().map(|p| p.global_number(10)).collect::<Vec<_>>(), vec![11, 12]);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::tables::is_separator_row;
+
+// 跨页拼接：段落或表格延续到下一页时，把下一页的开头接到上一页末尾，
+// 原来的页面标记改为行内锚点 (如 <!-- page 5 -->) 以便追溯
+
+// 句末标点，出现在上一页末尾时说明段落已经结束
+const TERMINAL_PUNCTUATION: &[char] = &['.', '!', '?', ':', ';', '。', '！', '？', '：', '；', '…'];
+// 句末标点之后可能跟着的引号和括号
+const CLOSING_MARKS: &[char] = &['"', '\'', ')', ']', '”', '’', '）', '」', '』'];
+
+pub fn is_cjk(c: char) -> bool {
+    matches!(c as u32, 0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF)
+}
+
+// 普通文本行：不是标题、表格、图片、代码块、引用、注释或列表
+fn is_text_line(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && !trimmed.starts_with(['#', '|', '!', '`', '>', '<', '-', '*', '+'])
+}
+
+fn is_table_line(line: &str) -> bool {
+    line.trim_start().starts_with('|')
+}
+
+fn column_count(line: &str) -> usize {
+    line.trim().trim_matches('|').split('|').count()
+}
+
+// 在行的第一个单元格开头插入锚点
+fn anchor_row(row: &str, anchor: &str) -> String {
+    if anchor.is_empty() {
+        return row.to_string();
+    }
+    match row.split_once('|') {
+        Some((before, after)) => format!("{}| {} {}", before, anchor, after.trim_start()),
+        None => row.to_string(),
+    }
+}
+
+// 尝试把 next 拼接到 prev 末尾；不构成跨页延续时返回 false，prev 保持不变。
+// 只检查 prev 的末尾并就地追加，逐页拼接整篇文档时不会反复复制已有内容
+pub fn join(prev: &mut String, next: &str, anchor: &str) -> bool {
+    let Some((keep, tail)) = continuation(prev.trim_end(), next.trim_start(), anchor) else {
+        return false;
+    };
+    prev.truncate(keep);
+    prev.push_str(&tail);
+    true
+}
+
+// 拼接方式：(prev 保留的字节数, 追加到其后的内容)
+fn continuation(prev: &str, next: &str, anchor: &str) -> Option<(usize, String)> {
+    let last_line = prev.lines().next_back()?;
+    let first_line = next.lines().next()?;
+
+    if is_table_line(last_line) && is_table_line(first_line) {
+        return join_tables(prev, next, anchor);
+    }
+    if !is_text_line(last_line) || !is_text_line(first_line) {
+        return None;
+    }
+
+    let first_char = first_line.chars().next()?;
+    let last_char = last_line.trim_end_matches(CLOSING_MARKS).chars().last()?;
+    if TERMINAL_PUNCTUATION.contains(&last_char) {
+        return None;
+    }
+
+    // 被连字符拆开的单词：compre- / hension → comprehension，锚点放在单词之后
+    if let Some(stem) = prev.strip_suffix('-') {
+        let before_hyphen = stem.chars().last()?;
+        if before_hyphen.is_alphabetic() && first_char.is_lowercase() {
+            let (word, rest) = next.split_at(next.find(char::is_whitespace).unwrap_or(next.len()));
+            return Some((stem.len(), format!("{}{}{}", word, anchor, rest)));
+        }
+        return None;
+    }
+
+    // 中日韩文字之间不加空格；其他语言只在下一页以小写字母开头时拼接
+    if is_cjk(last_char) && is_cjk(first_char) {
+        return Some((prev.len(), format!("{}{}", anchor, next)));
+    }
+    if first_char.is_lowercase() {
+        return Some((prev.len(), format!("{} {}", anchor, next)));
+    }
+    None
+}
+
+// 列数相同的表格合并为一个；下一页重复的表头被去掉
+fn join_tables(prev: &str, next: &str, anchor: &str) -> Option<(usize, String)> {
+    let prev_table: Vec<&str> = prev.lines().rev().take_while(|l| is_table_line(l)).collect();
+    let prev_header = *prev_table.last()?;
+    let next_lines: Vec<&str> = next.lines().collect();
+    let table_len = next_lines.iter().take_while(|l| is_table_line(l)).count();
+    if column_count(prev_header) != column_count(next_lines[0]) {
+        return None;
+    }
+
+    let mut rows: Vec<&str> = next_lines[..table_len].to_vec();
+    if rows.len() > 1 && is_separator_row(rows[1]) {
+        // 不同的表头是被识别成表头的第一行数据，保留
+        if rows[0].trim() == prev_header.trim() {
+            rows.drain(..2);
+        } else {
+            rows.remove(1);
+        }
+    }
+
+    let mut out = String::new();
+    for (i, row) in rows.iter().enumerate() {
+        out.push('\n');
+        if i == 0 {
+            out.push_str(&anchor_row(row, anchor));
+        } else {
+            out.push_str(row);
+        }
+    }
+    if rows.is_empty() {
+        out.push_str(anchor);
+    }
+    let rest = next_lines[table_len..].join("\n");
+    if !rest.trim().is_empty() {
+        out.push_str("\n\n");
+        out.push_str(rest.trim_start());
+    }
+    Some((prev.len(), out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANCHOR: &str = "<!-- page 2 -->";
+
+    fn joined(prev: &str, next: &str, anchor: &str) -> Option<String> {
+        let mut out = prev.to_string();
+        join(&mut out, next, anchor).then_some(out)
+    }
+
+    #[test]
+    fn sentences_words_and_tables_are_joined() {
+        assert_eq!(joined("The quick brown", "fox jumps.", ANCHOR).unwrap(), "The quick brown<!-- page 2 --> fox jumps.");
+        assert_eq!(joined("a compre-", "hension test", ANCHOR).unwrap(), "a comprehension<!-- page 2 --> test");
+        assert_eq!(joined("这是一个", "测试。", ANCHOR).unwrap(), "这是一个<!-- page 2 -->测试。");
+        assert!(joined("Done.", "next page", ANCHOR).is_none());
+        // 未拼接时上一页保持原样 (包括末尾空白)
+        let mut out = "Done.\n".to_string();
+        assert!(!join(&mut out, "next page", ANCHOR));
+        assert_eq!(out, "Done.\n");
+        assert!(joined("Intro", "New Paragraph", ANCHOR).is_none());
+        assert!(joined("# Heading", "text", ANCHOR).is_none());
+
+        let prev = "Text\n\n| a | b |\n|---|---|\n| 1 | 2 |";
+        let repeated_header = "| a | b |\n|---|---|\n| 3 | 4 |\n\nAfter";
+        assert_eq!(joined(prev, repeated_header, ANCHOR).unwrap(),
+            "Text\n\n| a | b |\n|---|---|\n| 1 | 2 |\n| <!-- page 2 --> 3 | 4 |\n\nAfter");
+        let promoted_row = "| 3 | 4 |\n|---|---|\n| 5 | 6 |";
+        assert_eq!(joined(prev, promoted_row, ANCHOR).unwrap(),
+            "Text\n\n| a | b |\n|---|---|\n| 1 | 2 |\n| <!-- page 2 --> 3 | 4 |\n| 5 | 6 |");
+        assert!(joined(prev, "| x | y | z |\n|---|---|---|", ANCHOR).is_none());
+    }
+}
@@ -0,0 +1,169 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use crate::export::{self, SavedPage};
+
+// CSV 文件所在的子目录
+pub const TABLES_DIR: &str = "tables";
+
+// 从页面 markdown 中解析出的一个表格
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub page: u32,
+    // 该页中的第几个表格 (从 1 开始)
+    pub index: usize,
+    // 第一行为表头
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    // 文件名和工作表名：page3_table1
+    pub fn name(&self) -> String {
+        format!("page{}_table{}", self.page, self.index)
+    }
+}
+
+// 表头下方的分隔行，如 |---|:--:|
+pub fn is_separator_row(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.contains('-') && trimmed.chars().all(|c| matches!(c, '|' | '-' | ':' | ' '))
+}
+
+// 拆分一行单元格，支持 \| 转义
+fn split_cells(line: &str) -> Vec<String> {
+    let trimmed = line.trim();
+    let inner = trimmed.strip_prefix('|').unwrap_or(trimmed);
+    let inner = inner.strip_suffix('|').filter(|s| !s.ends_with('\\')).unwrap_or(inner);
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push('|');
+                chars.next();
+            }
+            '|' => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            c => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+// 解析一页中的 GFM 表格：以 | 开头的连续行，第二行为分隔行
+pub fn parse_tables(page: &SavedPage) -> Vec<Table> {
+    let lines: Vec<&str> = page.markdown.lines().collect();
+    let mut tables = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let len = lines[i..].iter().take_while(|l| l.trim_start().starts_with('|')).count();
+        if len >= 2 && is_separator_row(lines[i + 1]) {
+            let rows = lines[i..i + len].iter()
+                .enumerate()
+                .filter(|(n, _)| *n != 1)
+                .map(|(_, l)| split_cells(l))
+                .collect();
+            tables.push(Table { page: page.number, index: tables.len() + 1, rows });
+        }
+        i += len.max(1);
+    }
+    tables
+}
+
+pub fn extract_tables(pages: &[SavedPage]) -> Vec<Table> {
+    pages.iter().flat_map(parse_tables).collect()
+}
+
+// 每个表格一个 CSV 文件，写入 tables/ 子目录
+pub fn write_csv(tables: &[Table], out_dir: &Path) -> Result<Vec<PathBuf>> {
+    if tables.is_empty() {
+        return Ok(Vec::new());
+    }
+    let dir = out_dir.join(TABLES_DIR);
+    std::fs::create_dir_all(&dir)?;
+    let mut written = Vec::new();
+    for table in tables {
+        let path = dir.join(format!("{}.csv", table.name()));
+        let csv: String = table.rows.iter().map(|r| export::csv_row(r)).collect();
+        std::fs::write(&path, csv)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+// 财务报表中的数字：1,234.56、-5，以及括号表示的负数 (1,234)
+fn parse_number(cell: &str) -> Option<f64> {
+    let mut text = cell.trim().replace([',', ' '], "");
+    let negative = text.starts_with('(') && text.ends_with(')');
+    if negative {
+        text = text[1..text.len() - 1].to_string();
+    }
+    if text.is_empty() || !text.chars().any(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    if !text.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+')) {
+        return None;
+    }
+    let value: f64 = text.parse().ok()?;
+    Some(if negative { -value } else { value })
+}
+
+// 所有表格写入一个工作簿，每个表格一个工作表；数字单元格写为数值
+pub fn write_xlsx(tables: &[Table], path: &Path) -> Result<bool> {
+    if tables.is_empty() {
+        return Ok(false);
+    }
+    let mut workbook = rust_xlsxwriter::Workbook::new();
+    let header = rust_xlsxwriter::Format::new().set_bold();
+    for table in tables {
+        let sheet = workbook.add_worksheet();
+        sheet.set_name(table.name())?;
+        for (r, row) in table.rows.iter().enumerate() {
+            for (c, cell) in row.iter().enumerate() {
+                let (r, c) = (r as u32, c as u16);
+                match parse_number(cell) {
+                    _ if r == 0 => sheet.write_string_with_format(r, c, cell, &header)?,
+                    Some(value) => sheet.write_number(r, c, value)?,
+                    None => sheet.write_string(r, c, cell)?,
+                };
+            }
+        }
+    }
+    workbook.save(path)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tables_are_parsed_and_written() {
+        let page = SavedPage {
+            number: 3,
+            markdown: "Intro\n\n| Item | 2023 |\n|:---|---:|\n| Revenue | 1,200.50 |\n| Loss \\| net | (300) |\n\n| not a table |\n\n| a |\n|---|\n| b |".into(),
+            provider: "mock".into(),
+            ..Default::default()
+        };
+
+        let tables = extract_tables(&[page]);
+
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].name(), "page3_table1");
+        assert_eq!(tables[0].rows, vec![
+            vec!["Item".to_string(), "2023".to_string()],
+            vec!["Revenue".to_string(), "1,200.50".to_string()],
+            vec!["Loss | net".to_string(), "(300)".to_string()],
+        ]);
+        assert_eq!(tables[1].name(), "page3_table2");
+        assert_eq!(parse_number("1,200.50"), Some(1200.5));
+        assert_eq!(parse_number("(300)"), Some(-300.0));
+        assert_eq!(parse_number("2023年"), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let csv = write_csv(&tables, dir.path()).unwrap();
+        assert_eq!(std::fs::read_to_string(&csv[0]).unwrap(), "Item,2023\r\nRevenue,\"1,200.50\"\r\nLoss | net,(300)\r\n");
+        assert!(write_xlsx(&tables, &dir.path().join("tables.xlsx")).unwrap());
+        assert!(!write_xlsx(&[], &dir.path().join("none.xlsx")).unwrap());
+    }
+}
@@ -0,0 +1,193 @@
+use pulldown_cmark::{CowStr, Event, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use crate::export::SavedDocument;
+
+// 由识别出的标题生成目录，插入在 Markdown 和 HTML 导出的开头 (不含页面标记)；
+// 目前没有 EPUB/PDF 导出，新增时可直接用 build 的结果作为导航
+
+// 源 PDF 书签中的一项
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OutlineEntry {
+    pub level: usize,
+    pub title: String,
+    // 文档中的页码 (从 1 开始)
+    pub page: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TocEntry {
+    pub level: usize,
+    pub title: String,
+    // 没有对应的标题时为 None，只显示文字
+    pub anchor: Option<String>,
+}
+
+// 与 GitHub 相同的锚点规则：小写，去掉标点，空格变为 -，重复的加 -1、-2 ...
+#[derive(Default)]
+struct Slugger {
+    used: HashMap<String, usize>,
+}
+
+impl Slugger {
+    fn slug(&mut self, text: &str) -> String {
+        let base: String = text.trim()
+            .chars()
+            .flat_map(char::to_lowercase)
+            .filter(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_'))
+            .map(|c| if c == ' ' { '-' } else { c })
+            .collect();
+        let count = self.used.entry(base.clone()).or_insert(0);
+        let slug = if *count == 0 { base.clone() } else { format!("{}-{}", base, count) };
+        *count += 1;
+        slug
+    }
+}
+
+// 文档中所有标题：(级别, 文字, 锚点)，顺序与渲染结果一致
+fn headings(markdown: &str) -> Vec<(usize, String, String)> {
+    let mut slugger = Slugger::default();
+    let mut headings = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+    for event in Parser::new_ext(markdown, pulldown_cmark::Options::ENABLE_TABLES) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => current = Some((level as usize, String::new())),
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, title)) = current.as_mut() {
+                    title.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((level, title)) = current.take() {
+                    let anchor = slugger.slug(&title);
+                    headings.push((level, title, anchor));
+                }
+            }
+            _ => {}
+        }
+    }
+    headings
+}
+
+// 由页面标记生成的标题文字，不进入目录
+fn marker_titles(doc: &SavedDocument) -> HashSet<String> {
+    doc.pages.iter()
+        .enumerate()
+        .filter_map(|(i, p)| doc.page_marker.render(p.number, i == 0))
+        .filter(|m| m.starts_with('#'))
+        .map(|m| m.trim_start_matches('#').trim().to_string())
+        .collect()
+}
+
+// body 为渲染后的正文 (含页面标记)；有 PDF 书签时以书签为目录，能对应到标题的书签带链接
+pub fn build(doc: &SavedDocument, body: &str) -> Vec<TocEntry> {
+    let headings = headings(body);
+    let markers = marker_titles(doc);
+    if doc.outline.is_empty() {
+        return headings.into_iter()
+            .filter(|(_, title, _)| !markers.contains(title))
+            .map(|(level, title, anchor)| TocEntry { level, title, anchor: Some(anchor) })
+            .collect();
+    }
+
+    let find = |text: &str| headings.iter()
+        .find(|(_, title, _)| title.trim().eq_ignore_ascii_case(text.trim()))
+        .map(|(_, _, anchor)| anchor.clone());
+    doc.outline.iter()
+        .map(|entry| TocEntry {
+            level: entry.level,
+            title: entry.title.clone(),
+            // 找不到同名标题时链接到该页的标题式页面标记
+            anchor: find(&entry.title).or_else(|| {
+                let index = doc.pages.iter().position(|p| p.number == entry.page)?;
+                let marker = doc.page_marker.render(entry.page, index == 0)?;
+                marker.starts_with('#').then(|| find(marker.trim_start_matches('#')))?
+            }),
+        })
+        .collect()
+}
+
+fn escape_link_text(text: &str) -> String {
+    text.replace('[', "\\[").replace(']', "\\]")
+}
+
+// 嵌套列表形式的目录，后接空行；没有条目时为空
+pub fn render(entries: &[TocEntry]) -> String {
+    let Some(min_level) = entries.iter().map(|e| e.level).min() else { return String::new() };
+    let mut out = String::new();
+    for entry in entries {
+        let indent = "  ".repeat(entry.level - min_level);
+        match &entry.anchor {
+            Some(anchor) => out.push_str(&format!("{}- [{}](#{})\n", indent, escape_link_text(&entry.title), anchor)),
+            None => out.push_str(&format!("{}- {}\n", indent, entry.title)),
+        }
+    }
+    out.push('\n');
+    out
+}
+
+// HTML 导出时为标题加上 id，与目录中的锚点一致
+pub fn with_heading_ids(events: Vec<Event<'_>>) -> Vec<Event<'_>> {
+    let mut slugger = Slugger::default();
+    let mut events = events;
+    let mut i = 0;
+    while i < events.len() {
+        if let Event::Start(Tag::Heading { .. }) = &events[i] {
+            let mut title = String::new();
+            for event in &events[i + 1..] {
+                match event {
+                    Event::Text(text) | Event::Code(text) => title.push_str(text),
+                    Event::End(TagEnd::Heading(_)) => break,
+                    _ => {}
+                }
+            }
+            let slug = slugger.slug(&title);
+            if let Event::Start(Tag::Heading { id, .. }) = &mut events[i] {
+                *id = Some(CowStr::from(slug));
+            }
+        }
+        i += 1;
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{PageMarker, SavedPage};
+
+    fn doc(pages: &[&str]) -> SavedDocument {
+        SavedDocument {
+            source: "doc.pdf".into(),
+            provider: "mock".into(),
+            pages: pages.iter().enumerate().map(|(i, md)| SavedPage {
+                number: i as u32 + 1,
+                markdown: md.to_string(),
+                provider: "mock".into(),
+                ..Default::default()
+            }).collect(),
+            page_marker: PageMarker::Heading,
+            toc: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn toc_skips_page_markers_and_matches_outline() {
+        let mut doc = doc(&["# Intro\n\ntext", "## Details `x`\n\n# Intro"]);
+        let body = crate::export::render_pages(&doc.pages, &doc.page_marker, false);
+
+        assert_eq!(render(&build(&doc, &body)), "- [Intro](#intro)\n  - [Details x](#details-x)\n- [Intro](#intro-1)\n\n");
+        let mut html = String::new();
+        pulldown_cmark::html::push_html(&mut html, with_heading_ids(Parser::new(&body).collect()).into_iter());
+        assert!(html.contains("<h2 id=\"details-x\">Details <code>x</code></h2>"));
+        assert!(html.contains("<h1 id=\"intro-1\">Intro</h1>"));
+
+        doc.outline = vec![
+            OutlineEntry { level: 1, title: "DETAILS X".into(), page: 2 },
+            OutlineEntry { level: 1, title: "Appendix".into(), page: 2 },
+            OutlineEntry { level: 1, title: "Missing".into(), page: 9 },
+        ];
+        assert_eq!(render(&build(&doc, &body)), "- [DETAILS X](#details-x)\n- [Appendix](#page-2)\n- Missing\n\n");
+    }
+}
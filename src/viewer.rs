@@ -0,0 +1,351 @@
+use eframe::egui;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::bundle;
+use crate::cleanup::{self, Removal};
+use crate::export::{self, ExportFormat, SavedDocument};
+use crate::i18n::I18n;
+use crate::pdf_utils;
+
+// 应用内的结果查看器：逐页渲染 markdown 和提取的图片，并支持手动修正
+pub struct ResultViewer {
+    documents: Vec<PathBuf>,
+    current_doc: usize,
+    out_dir: PathBuf,
+    doc: Option<SavedDocument>,
+    load_error: Option<String>,
+    page_index: usize,
+    editing: bool,
+    // 存在尚未保存的修改
+    dirty: bool,
+    status: Option<String>,
+    // 检测到的页眉页脚，删除前先预览
+    header_preview: Option<Vec<Removal>>,
+    // 按路径缓存的纹理；None 表示加载失败，不再重试
+    textures: HashMap<PathBuf, Option<egui::TextureHandle>>,
+}
+
+impl ResultViewer {
+    pub fn new(documents: Vec<PathBuf>) -> Self {
+        let mut viewer = Self {
+            documents,
+            current_doc: 0,
+            out_dir: PathBuf::new(),
+            doc: None,
+            load_error: None,
+            page_index: 0,
+            editing: false,
+            dirty: false,
+            status: None,
+            header_preview: None,
+            textures: HashMap::new(),
+        };
+        viewer.select_document(0);
+        viewer
+    }
+
+    fn select_document(&mut self, index: usize) {
+        self.current_doc = index;
+        self.page_index = 0;
+        self.dirty = false;
+        self.status = None;
+        self.header_preview = None;
+        self.textures.clear();
+        self.out_dir = self.documents.get(index).cloned().unwrap_or_default();
+        match SavedDocument::load(&self.out_dir) {
+            Ok(doc) => {
+                self.doc = Some(doc);
+                self.load_error = None;
+            }
+            Err(e) => {
+                self.doc = None;
+                self.load_error = Some(e.to_string());
+            }
+        }
+    }
+
+    // 返回 false 表示窗口已被关闭
+    pub fn show(&mut self, ctx: &egui::Context, i18n: &I18n, export_formats: &[ExportFormat]) -> bool {
+        let mut open = true;
+        egui::Window::new(i18n.t("viewer_title"))
+            .open(&mut open)
+            .default_size([900.0, 700.0])
+            .show(ctx, |ui| {
+                self.render_toolbar(ui, i18n);
+                self.render_edit_bar(ui, i18n, export_formats);
+                self.render_header_preview(ui, i18n);
+                ui.separator();
+
+                if let Some(e) = &self.load_error {
+                    ui.colored_label(ui.visuals().error_fg_color, e);
+                    return;
+                }
+                self.render_page(ui);
+            });
+        open
+    }
+
+    fn render_toolbar(&mut self, ui: &mut egui::Ui, i18n: &I18n) {
+        ui.horizontal(|ui| {
+            // 有未保存的修改时不允许切换文档
+            if self.documents.len() > 1 {
+                ui.add_enabled_ui(!self.dirty, |ui| self.render_document_combo(ui));
+                ui.separator();
+            }
+
+            let page_count = self.doc.as_ref().map(|d| d.pages.len()).unwrap_or(0);
+            if page_count == 0 {
+                return;
+            }
+
+            if ui.add_enabled(self.page_index > 0, egui::Button::new("◀")).clicked() {
+                self.page_index -= 1;
+            }
+            ui.label(i18n.t("page"));
+            let mut page_number = self.page_index + 1;
+            ui.add(egui::DragValue::new(&mut page_number).range(1..=page_count));
+            self.page_index = page_number.clamp(1, page_count) - 1;
+            ui.label(format!("/ {}", page_count));
+            if ui.add_enabled(self.page_index + 1 < page_count, egui::Button::new("▶")).clicked() {
+                self.page_index += 1;
+            }
+
+            if let Some(page) = self.doc.as_ref().and_then(|d| d.pages.get(self.page_index)) {
+                if !page.provider.is_empty() {
+                    ui.label(format!("({})", page.provider));
+                }
+            }
+
+            ui.separator();
+            if ui.button(i18n.t("open_folder")).clicked() {
+                let _ = open::that(&self.out_dir);
+            }
+        });
+    }
+
+    fn render_document_combo(&mut self, ui: &mut egui::Ui) {
+        let mut selected = self.current_doc;
+        egui::ComboBox::from_id_source("viewer_doc_combo")
+            .selected_text(dir_label(&self.out_dir))
+            .show_ui(ui, |ui| {
+                for (i, dir) in self.documents.iter().enumerate() {
+                    ui.selectable_value(&mut selected, i, dir_label(dir));
+                }
+            });
+        if selected != self.current_doc {
+            self.select_document(selected);
+        }
+    }
+
+    fn render_edit_bar(&mut self, ui: &mut egui::Ui, i18n: &I18n, export_formats: &[ExportFormat]) {
+        if self.doc.is_none() {
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.toggle_value(&mut self.editing, i18n.t("edit"));
+            if ui.add_enabled(self.dirty, egui::Button::new(i18n.t("save"))).clicked() {
+                self.status = Some(match self.save(export_formats) {
+                    Ok(()) => i18n.t("corrections_saved").to_string(),
+                    Err(e) => format!("Error: {}", e),
+                });
+            }
+            if ui.add_enabled(self.dirty, egui::Button::new(i18n.t("discard_changes"))).clicked() {
+                let page_index = self.page_index;
+                self.select_document(self.current_doc);
+                self.page_index = page_index;
+            }
+            let mut previewing = self.header_preview.is_some();
+            if ui.toggle_value(&mut previewing, i18n.t("headers_footers")).changed() {
+                self.header_preview = match (previewing, &self.doc) {
+                    (true, Some(doc)) => Some(cleanup::detect(&doc.pages)),
+                    _ => None,
+                };
+            }
+            if self.dirty {
+                ui.label(i18n.t("unsaved_changes"));
+            } else if let Some(status) = &self.status {
+                ui.label(status);
+            }
+        });
+    }
+
+    fn render_header_preview(&mut self, ui: &mut egui::Ui, i18n: &I18n) {
+        let Some(removals) = &self.header_preview else { return };
+        ui.separator();
+        if removals.is_empty() {
+            ui.label(i18n.t("no_headers_found"));
+            return;
+        }
+        let pages = removals.iter().map(|r| r.page).collect::<std::collections::HashSet<_>>().len();
+        ui.label(format!("{} {}", pages, i18n.t("pages_count")));
+        egui::ScrollArea::vertical().id_source("header_preview").max_height(150.0).show(ui, |ui| {
+            let Some(doc) = &self.doc else { return };
+            for removal in removals {
+                let number = doc.pages.get(removal.page).map(|p| p.number).unwrap_or_default();
+                ui.label(egui::RichText::new(format!("{} {}: {}", i18n.t("page"), number, removal.text)).monospace().strikethrough());
+            }
+        });
+        if ui.button(i18n.t("remove_lines")).clicked() {
+            if let (Some(doc), Some(removals)) = (self.doc.as_mut(), self.header_preview.take()) {
+                cleanup::apply(&mut doc.pages, &removals);
+                self.dirty = true;
+            }
+        }
+    }
+
+    // 写回 result.json，并从修正后的页面重新生成 Markdown 和其他选中的格式
+    fn save(&mut self, export_formats: &[ExportFormat]) -> anyhow::Result<()> {
+        let Some(doc) = &self.doc else { return Ok(()) };
+        doc.save(&self.out_dir)?;
+
+        let mut formats = vec![ExportFormat::Markdown];
+        formats.extend(export_formats.iter().copied().filter(|f| *f != ExportFormat::Markdown));
+        export::export_document(doc, &self.out_dir, &formats)?;
+        bundle::refresh_document_bundle(&self.out_dir)?;
+
+        self.dirty = false;
+        Ok(())
+    }
+
+    fn render_page(&mut self, ui: &mut egui::Ui) {
+        let Some(doc) = &self.doc else { return };
+        if doc.pages.get(self.page_index).is_none() {
+            return;
+        }
+
+        // 图片输入只有一页，把原图放在识别结果旁边便于核对
+        let source = doc.source.clone();
+        if pdf_utils::is_image_file(&source) && source.exists() {
+            ui.columns(2, |columns| {
+                egui::ScrollArea::vertical().id_source("viewer_source").show(&mut columns[0], |ui| {
+                    self.render_image(ui, &source);
+                });
+                egui::ScrollArea::vertical().id_source("viewer_markdown").show(&mut columns[1], |ui| {
+                    self.render_page_content(ui);
+                });
+            });
+        } else {
+            egui::ScrollArea::vertical().id_source("viewer_markdown").show(ui, |ui| {
+                self.render_page_content(ui);
+            });
+        }
+    }
+
+    fn render_page_content(&mut self, ui: &mut egui::Ui) {
+        let page_index = self.page_index;
+        if self.editing {
+            if let Some(page) = self.doc.as_mut().and_then(|d| d.pages.get_mut(page_index)) {
+                let editor = egui::TextEdit::multiline(&mut page.markdown)
+                    .code_editor()
+                    .desired_width(f32::INFINITY)
+                    .desired_rows(30);
+                if ui.add(editor).changed() {
+                    self.dirty = true;
+                    // 预览的行号已失效
+                    self.header_preview = None;
+                }
+            }
+        } else if let Some(markdown) = self.doc.as_ref().and_then(|d| d.pages.get(page_index)).map(|p| p.markdown.clone()) {
+            self.render_markdown(ui, &markdown);
+        }
+    }
+
+    // 简单的逐行渲染：标题、表格、图片和普通段落
+    fn render_markdown(&mut self, ui: &mut egui::Ui, markdown: &str) {
+        for line in markdown.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                ui.add_space(4.0);
+                continue;
+            }
+
+            if let Some((level, text)) = parse_heading(trimmed) {
+                let size = match level {
+                    1 => 24.0,
+                    2 => 20.0,
+                    3 => 17.0,
+                    _ => 15.0,
+                };
+                ui.label(egui::RichText::new(text).size(size).strong());
+                continue;
+            }
+
+            if trimmed.starts_with('|') {
+                ui.label(egui::RichText::new(trimmed).monospace());
+                continue;
+            }
+
+            for segment in split_images(trimmed) {
+                match segment {
+                    Segment::Text(text) => {
+                        if !text.trim().is_empty() {
+                            ui.label(text);
+                        }
+                    }
+                    Segment::Image(rel) => {
+                        let path = self.out_dir.join(rel);
+                        self.render_image(ui, &path);
+                    }
+                }
+            }
+        }
+    }
+
+    fn render_image(&mut self, ui: &mut egui::Ui, path: &Path) {
+        let texture = self.textures
+            .entry(path.to_path_buf())
+            .or_insert_with(|| load_texture(ui.ctx(), path))
+            .clone();
+
+        match texture {
+            Some(texture) => {
+                let size = texture.size_vec2();
+                let scale = (ui.available_width() / size.x).min(1.0);
+                ui.image((texture.id(), size * scale));
+            }
+            None => {
+                ui.label(format!("[{}]", path.display()));
+            }
+        }
+    }
+}
+
+fn dir_label(dir: &Path) -> String {
+    dir.file_name().unwrap_or_default().to_string_lossy().to_string()
+}
+
+fn load_texture(ctx: &egui::Context, path: &Path) -> Option<egui::TextureHandle> {
+    let img = ::image::open(path).ok()?.to_rgba8();
+    let size = [img.width() as usize, img.height() as usize];
+    let color_image = egui::ColorImage::from_rgba_unmultiplied(size, img.as_raw());
+    Some(ctx.load_texture(path.to_string_lossy(), color_image, egui::TextureOptions::LINEAR))
+}
+
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if (1..=6).contains(&level) && line[level..].starts_with(' ') {
+        Some((level, line[level..].trim()))
+    } else {
+        None
+    }
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Image(&'a str),
+}
+
+// 把一行拆分为文本和 ![alt](path) 图片
+fn split_images(line: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = line;
+    while let Some(start) = rest.find("![") {
+        let Some(mid) = rest[start..].find("](").map(|i| start + i) else { break };
+        let Some(end) = rest[mid..].find(')').map(|i| mid + i) else { break };
+        segments.push(Segment::Text(&rest[..start]));
+        segments.push(Segment::Image(&rest[mid + 2..end]));
+        rest = &rest[end + 1..];
+    }
+    segments.push(Segment::Text(rest));
+    segments
+}